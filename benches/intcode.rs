@@ -0,0 +1,156 @@
+//! Times the brute-force searches of days 2 and 7 with the intcode interpreter, in the default
+//! persistent memory and in a flat one.
+//!
+//! Run with `cargo bench`. Each time is compared with the one of the interpreter before
+//! instructions were decoded ahead of time, recorded on the development machine, so the speed-up
+//! is only meaningful there.
+
+use advent2019::intcode::{FlatMemory, Intcode, Memory, PersistentMemory, Pipe, Program};
+use std::fs::read_to_string;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const MINIMUM_DURATION: Duration = Duration::from_secs(2);
+
+/// The mean durations of the searches with the interpreter decoding every instruction as it ran
+/// it, taken over three runs.
+const REFERENCE_DAY2: Duration = Duration::from_micros(31_000);
+const REFERENCE_DAY7: Duration = Duration::from_micros(1_250);
+const REFERENCE_DAY7_LOOPED: Duration = Duration::from_micros(4_100);
+
+fn main() {
+    let day2 = load("day2");
+    let day7 = load("day7");
+    let settings = permutations(&[0, 1, 2, 3, 4]);
+    let looped_settings = permutations(&[5, 6, 7, 8, 9]);
+    bench_memory::<PersistentMemory>("persistent", &day2, &day7, &settings, &looped_settings);
+    bench_memory::<FlatMemory>("flat", &day2, &day7, &settings, &looped_settings);
+}
+
+/// Times the searches with the programs moved to the memory backend once, then cloned.
+fn bench_memory<M: Memory<i64>>(
+    memory: &str,
+    day2: &Intcode,
+    day7: &Intcode,
+    settings: &[Vec<i64>],
+    looped_settings: &[Vec<i64>],
+) {
+    let day2: Intcode<i64, M> = day2.with_memory();
+    let day7: Intcode<i64, M> = day7.with_memory();
+    bench(
+        &format!("day 2 noun and verb search, {}", memory),
+        REFERENCE_DAY2,
+        || find_noun_and_verb(&day2),
+    );
+    bench(
+        &format!("day 7 phase settings sweep, {}", memory),
+        REFERENCE_DAY7,
+        || settings.iter().map(|s| amplify(&day7, s, false)).max(),
+    );
+    bench(
+        &format!("day 7 looped phase settings sweep, {}", memory),
+        REFERENCE_DAY7_LOOPED,
+        || {
+            looped_settings
+                .iter()
+                .map(|s| amplify(&day7, s, true))
+                .max()
+        },
+    );
+}
+
+fn load(day: &str) -> Intcode {
+    read_to_string(format!("src/input/{}", day))
+        .expect("Could not read the input")
+        .parse()
+        .expect("Could not parse the input")
+}
+
+/// Runs the function until the minimum duration is reached, and prints its mean duration with its
+/// speed-up over the reference.
+fn bench<T, F: FnMut() -> T>(name: &str, reference: Duration, mut function: F) {
+    let start = Instant::now();
+    let mut iterations = 0;
+    while start.elapsed() < MINIMUM_DURATION {
+        black_box(function());
+        iterations += 1;
+    }
+    let mean = start.elapsed() / iterations;
+    println!(
+        "{:<48} {:>12?} ({} iterations), {:.1}x faster than {:?}",
+        name,
+        mean,
+        iterations,
+        reference.as_secs_f64() / mean.as_secs_f64(),
+        reference
+    );
+}
+
+fn find_noun_and_verb<M: Memory<i64>>(intcode: &Intcode<i64, M>) -> Option<i64> {
+    for noun in 0..100 {
+        for verb in 0..100 {
+            let mut intcode = intcode.clone();
+            intcode.write(1, noun);
+            intcode.write(2, verb);
+            let mut program = Program::new(intcode);
+            program.run().unwrap();
+            if program.intcode().read(0) == 19_690_720 {
+                return Some(noun * 100 + verb);
+            }
+        }
+    }
+    None
+}
+
+fn amplify<M: Memory<i64>>(
+    intcode: &Intcode<i64, M>,
+    settings: &[i64],
+    looped: bool,
+) -> Option<i64> {
+    let mut amplifiers: Vec<Program<i64, M>> = settings
+        .iter()
+        .map(|s| {
+            let program = Program::new(intcode.clone());
+            program.write(*s);
+            program
+        })
+        .collect();
+    for i in 1..amplifiers.len() {
+        let (previous, next) = amplifiers.split_at_mut(i);
+        previous[i - 1].connect(&next[0]);
+    }
+    let output = Pipe::new();
+    let (first, last) = amplifiers.split_at_mut(settings.len() - 1);
+    match looped {
+        true => last[0].connect(&first[0]),
+        false => last[0].set_output(&output),
+    }
+    amplifiers[0].write(0);
+    let mut over = false;
+    while !over {
+        over = true;
+        for amplifier in amplifiers.iter_mut() {
+            over &= amplifier.run().unwrap();
+        }
+    }
+    match looped {
+        true => amplifiers[settings.len() - 1].take_output(),
+        false => output.read(),
+    }
+}
+
+fn permutations(values: &[i64]) -> Vec<Vec<i64>> {
+    if values.len() <= 1 {
+        return vec![values.to_vec()];
+    }
+    let mut result = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        let mut rest = values.to_vec();
+        rest.remove(i);
+        for mut permutation in permutations(&rest) {
+            permutation.insert(0, *value);
+            result.push(permutation);
+        }
+    }
+    result
+}
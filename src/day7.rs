@@ -4,7 +4,7 @@ use crate::intcode::{Intcode, Pipe, Program};
 pub fn execute() {
     let intcode: Intcode = read_input("day7")
         .split(",")
-        .map(|i| i.trim().parse::<i32>().unwrap())
+        .map(|i| i.trim().parse::<i64>().unwrap())
         .collect();
     let highest_signal = find_best_phase_setting(&intcode);
     println!("7:1 Hightest signal: {}", highest_signal.unwrap());
@@ -12,19 +12,19 @@ pub fn execute() {
     println!("7:1 Hightest signal: {}", highest_looped_signal.unwrap());
 }
 
-fn find_best_phase_setting(intcode: &Intcode) -> Option<i32> {
-    PermutationsGenerator::new((0..5 as i32).collect())
+fn find_best_phase_setting(intcode: &Intcode) -> Option<i64> {
+    PermutationsGenerator::new((0..5).collect())
         .map(|s| try_phase_settings(intcode, &s))
         .max()
 }
 
-fn find_best_looped_phase_setting(intcode: &Intcode) -> Option<i32> {
-    PermutationsGenerator::new((5..10 as i32).collect())
+fn find_best_looped_phase_setting(intcode: &Intcode) -> Option<i64> {
+    PermutationsGenerator::new((5..10).collect())
         .map(|s| try_looped_phase_settings(intcode, &s))
         .max()
 }
 
-fn try_phase_settings(intcode: &Intcode, settings: &Vec<i32>) -> i32 {
+fn try_phase_settings(intcode: &Intcode, settings: &Vec<i64>) -> i64 {
     let output_pipe = Pipe::new();
     let mut amplifier_a = Program::new(intcode.clone());
     let mut amplifier_b = Program::new(intcode.clone());
@@ -43,25 +43,25 @@ fn try_phase_settings(intcode: &Intcode, settings: &Vec<i32>) -> i32 {
     amplifier_d.write(settings[3]);
     amplifier_e.write(settings[4]);
     let mut results = [
-        amplifier_a.run(),
-        amplifier_b.run(),
-        amplifier_c.run(),
-        amplifier_d.run(),
-        amplifier_e.run(),
+        amplifier_a.run().unwrap(),
+        amplifier_b.run().unwrap(),
+        amplifier_c.run().unwrap(),
+        amplifier_d.run().unwrap(),
+        amplifier_e.run().unwrap(),
     ];
     while results.iter().any(|r| !r) {
         results = [
-            amplifier_a.run(),
-            amplifier_b.run(),
-            amplifier_c.run(),
-            amplifier_d.run(),
-            amplifier_e.run(),
+            amplifier_a.run().unwrap(),
+            amplifier_b.run().unwrap(),
+            amplifier_c.run().unwrap(),
+            amplifier_d.run().unwrap(),
+            amplifier_e.run().unwrap(),
         ];
     }
     output_pipe.read().unwrap()
 }
 
-fn try_looped_phase_settings(intcode: &Intcode, settings: &Vec<i32>) -> i32 {
+fn try_looped_phase_settings(intcode: &Intcode, settings: &Vec<i64>) -> i64 {
     let mut amplifier_a = Program::new(intcode.clone());
     let mut amplifier_b = Program::new(intcode.clone());
     let mut amplifier_c = Program::new(intcode.clone());
@@ -79,33 +79,33 @@ fn try_looped_phase_settings(intcode: &Intcode, settings: &Vec<i32>) -> i32 {
     amplifier_d.write(settings[3]);
     amplifier_e.write(settings[4]);
     let mut results = [
-        amplifier_a.run(),
-        amplifier_b.run(),
-        amplifier_c.run(),
-        amplifier_d.run(),
-        amplifier_e.run(),
+        amplifier_a.run().unwrap(),
+        amplifier_b.run().unwrap(),
+        amplifier_c.run().unwrap(),
+        amplifier_d.run().unwrap(),
+        amplifier_e.run().unwrap(),
     ];
     while results.iter().any(|r| !r) {
         results = [
-            amplifier_a.run(),
-            amplifier_b.run(),
-            amplifier_c.run(),
-            amplifier_d.run(),
-            amplifier_e.run(),
+            amplifier_a.run().unwrap(),
+            amplifier_b.run().unwrap(),
+            amplifier_c.run().unwrap(),
+            amplifier_d.run().unwrap(),
+            amplifier_e.run().unwrap(),
         ];
     }
-    amplifier_e.read().unwrap()
+    amplifier_e.take_output().unwrap()
 }
 
 struct PermutationsGenerator {
-    vector: Vec<i32>,
+    vector: Vec<i64>,
     c: Vec<usize>,
     i: usize,
     first: bool,
 }
 
 impl PermutationsGenerator {
-    fn new(vector: Vec<i32>) -> Self {
+    fn new(vector: Vec<i64>) -> Self {
         let size = vector.len();
         PermutationsGenerator {
             vector,
@@ -117,7 +117,7 @@ impl PermutationsGenerator {
 }
 
 impl Iterator for PermutationsGenerator {
-    type Item = Vec<i32>;
+    type Item = Vec<i64>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.first {
@@ -148,9 +148,9 @@ mod permutations_generator_should {
 
     #[test]
     fn return_all_permutations() {
-        let generator = PermutationsGenerator::new((0..3 as i32).collect());
+        let generator = PermutationsGenerator::new((0..3).collect());
 
-        let result: Vec<Vec<i32>> = generator.collect();
+        let result: Vec<Vec<i64>> = generator.collect();
 
         assert_eq!(
             result,
@@ -0,0 +1,221 @@
+use super::{Pipe, Value};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// A character that cannot be written to an ASCII program, with its position in the text, in
+/// characters.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AsciiError {
+    pub position: usize,
+    pub character: char,
+}
+
+impl Display for AsciiError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "'{}' at {} is not an ASCII character",
+            self.character, self.position
+        )
+    }
+}
+
+impl Error for AsciiError {}
+
+/// A part of the output of an ASCII program: text, or a value outside of the ASCII range, such as
+/// the answer of a puzzle, passed through as a number.
+#[derive(Clone, PartialEq, Debug)]
+pub enum AsciiChunk<V = i64> {
+    Text(String),
+    Value(V),
+}
+
+impl<V: Value> Display for AsciiChunk<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            AsciiChunk::Text(text) => write!(f, "{}", text),
+            AsciiChunk::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Encodes text into the values of its characters. Line endings are written as a single `\n`,
+/// the only one understood by ASCII programs. Nothing is encoded if the text holds a character
+/// outside of the ASCII range.
+pub fn encode<V: Value>(text: &str) -> Result<Vec<V>, AsciiError> {
+    let mut values = Vec::with_capacity(text.len());
+    let mut characters = text.chars().enumerate().peekable();
+    while let Some((position, character)) = characters.next() {
+        match character {
+            '\r' => {
+                characters.next_if(|(_, c)| *c == '\n');
+                values.push(V::from('\n' as i32));
+            }
+            _ if character.is_ascii() => values.push(V::from(character as i32)),
+            _ => {
+                return Err(AsciiError {
+                    position,
+                    character,
+                })
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Decodes values into text, split around the values outside of the ASCII range.
+pub fn decode<V: Value>(values: &[V]) -> Vec<AsciiChunk<V>> {
+    let mut chunks = Vec::new();
+    let mut text = String::new();
+    for value in values {
+        match value.to_i64().filter(|v| (0..128).contains(v)) {
+            Some(code) => text.push(code as u8 as char),
+            None => {
+                if !text.is_empty() {
+                    chunks.push(AsciiChunk::Text(text.split_off(0)));
+                }
+                chunks.push(AsciiChunk::Value(value.clone()));
+            }
+        }
+    }
+    if !text.is_empty() {
+        chunks.push(AsciiChunk::Text(text));
+    }
+    chunks
+}
+
+/// Writes text to the input of a program.
+#[derive(Clone)]
+pub struct AsciiInput<V = i64> {
+    pipe: Pipe<V>,
+}
+
+impl<V: Value> AsciiInput<V> {
+    pub fn new(pipe: &Pipe<V>) -> Self {
+        AsciiInput { pipe: pipe.clone() }
+    }
+
+    /// Writes the characters of the text, as given by `encode`.
+    pub fn write_str(&self, text: &str) -> Result<(), AsciiError> {
+        for value in encode(text)? {
+            self.pipe.write(value);
+        }
+        Ok(())
+    }
+
+    /// Writes a line of text, ending it with a `\n` unless it already ends with a line ending.
+    pub fn write_line(&self, line: &str) -> Result<(), AsciiError> {
+        self.write_str(line)?;
+        if !line.ends_with('\n') && !line.ends_with('\r') {
+            self.pipe.write(V::from(10));
+        }
+        Ok(())
+    }
+}
+
+/// Reads text from the output of a program.
+#[derive(Clone)]
+pub struct AsciiOutput<V = i64> {
+    pipe: Pipe<V>,
+}
+
+impl<V: Value> AsciiOutput<V> {
+    pub fn new(pipe: &Pipe<V>) -> Self {
+        AsciiOutput { pipe: pipe.clone() }
+    }
+
+    /// Reads the values waiting in the pipe, decoded by `decode`.
+    pub fn read(&self) -> Vec<AsciiChunk<V>> {
+        let values: Vec<V> = std::iter::from_fn(|| self.pipe.read()).collect();
+        decode(&values)
+    }
+
+    /// Reads the values waiting in the pipe as text, the values outside of the ASCII range
+    /// written as numbers.
+    pub fn read_string(&self) -> String {
+        self.read().iter().map(|c| c.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod ascii_should {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+    use crate::intcode::Program;
+
+    #[test]
+    fn encode_line_endings_as_a_single_newline() {
+        let values: Vec<i64> = encode("a\r\nb\rc\n").unwrap();
+
+        assert_eq!(values, vec![97, 10, 98, 10, 99, 10]);
+    }
+
+    #[test]
+    fn reject_a_character_outside_of_the_ascii_range() {
+        let result: Result<Vec<i64>, _> = encode("café");
+
+        assert_eq!(
+            result,
+            Err(AsciiError {
+                position: 3,
+                character: 'é'
+            })
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "'é' at 3 is not an ASCII character"
+        );
+    }
+
+    #[test]
+    fn report_the_position_of_a_character_in_the_text_given() {
+        let result: Result<Vec<i64>, _> = encode("a\r\nbé");
+
+        assert_eq!(result.unwrap_err().position, 4);
+    }
+
+    #[test]
+    fn pass_the_values_outside_of_the_ascii_range_through() {
+        let chunks = decode(&[72, 105, 10, 19_349_722, -1, 33]);
+
+        assert_eq!(
+            chunks,
+            vec![
+                AsciiChunk::Text("Hi\n".to_string()),
+                AsciiChunk::Value(19_349_722),
+                AsciiChunk::Value(-1),
+                AsciiChunk::Text("!".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn hold_a_conversation_with_a_program() {
+        // Outputs each character of a line in upper case, then 1000.
+        let mut program: Program = Program::new(
+            assemble(
+                "loop:  in [c]
+                        eq [c], #10, [t]
+                        jnz [t], #end
+                        add [c], #-32, [c]
+                        out [c]
+                        jz #0, #loop
+                 end:   out #1000
+                        hlt
+                 c:     .data 0
+                 t:     .data 0",
+            )
+            .unwrap(),
+        );
+        let output = Pipe::new();
+        program.set_output(&output);
+        let input = AsciiInput::new(program.input());
+        let output = AsciiOutput::new(&output);
+
+        input.write_line("hello").unwrap();
+        program.run().unwrap();
+
+        assert_eq!(output.read_string(), "HELLO1000");
+        assert!(output.read().is_empty());
+    }
+}
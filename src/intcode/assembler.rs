@@ -0,0 +1,495 @@
+use super::{Intcode, OpCode, ParameterMode, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// An error found while assembling a program, with the line (starting at 1) where it was found.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AssemblyError {
+    pub line: usize,
+    pub kind: AssemblyErrorKind,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AssemblyErrorKind {
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    WrongOperandCount { expected: usize, found: usize },
+    InvalidOperand(String),
+    InvalidExpression(String),
+    InvalidSymbol(String),
+    DuplicateSymbol(String),
+    UndefinedSymbol(String),
+    WriteInImmediateMode,
+    AddressOverflow(usize),
+}
+
+impl Display for AssemblyError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AssemblyErrorKind::UnknownMnemonic(m) => write!(f, "unknown mnemonic '{}'", m),
+            AssemblyErrorKind::UnknownDirective(d) => write!(f, "unknown directive '{}'", d),
+            AssemblyErrorKind::WrongOperandCount { expected, found } => {
+                write!(f, "expected {} operands, found {}", expected, found)
+            }
+            AssemblyErrorKind::InvalidOperand(o) => write!(f, "invalid operand '{}'", o),
+            AssemblyErrorKind::InvalidExpression(e) => write!(f, "invalid expression '{}'", e),
+            AssemblyErrorKind::InvalidSymbol(s) => write!(f, "invalid symbol name '{}'", s),
+            AssemblyErrorKind::DuplicateSymbol(s) => write!(f, "symbol '{}' is already defined", s),
+            AssemblyErrorKind::UndefinedSymbol(s) => write!(f, "undefined symbol '{}'", s),
+            AssemblyErrorKind::WriteInImmediateMode => write!(f, "cannot write in immediate mode"),
+            AssemblyErrorKind::AddressOverflow(a) => write!(f, "address {} does not fit", a),
+        }
+    }
+}
+
+impl Error for AssemblyError {}
+
+/// Assembles a program written in intcode assembly.
+///
+/// Each line holds an optional `label:`, then an instruction, a directive or nothing, then an
+/// optional `; comment`. Instructions use the mnemonics of `OpCode`, with operands in position
+/// (`[12]`), immediate (`#5`) or relative (`rb+3`) mode. Operands are expressions adding or
+/// subtracting numbers, labels and constants. The `.data` directive lists raw values and
+/// `.const NAME = expression` defines a constant.
+///
+/// ```text
+/// loop:   in [counter]
+///         jz [counter], #end
+///         out #LETTER
+///         jnz #1, #loop
+/// end:    hlt
+/// counter: .data 0
+/// .const LETTER = 65
+/// ```
+pub fn assemble<V: Value>(source: &str) -> Result<Intcode<V>, AssemblyError> {
+    let mut statements = Vec::new();
+    let mut constants = Vec::new();
+    let mut symbols: HashMap<String, V> = HashMap::new();
+    let mut address = 0;
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let error = |kind| AssemblyError { line, kind };
+        let (label, statement) = parse_line(text).map_err(error)?;
+        if let Some(label) = label {
+            let value = V::from_address(address)
+                .ok_or_else(|| error(AssemblyErrorKind::AddressOverflow(address)))?;
+            define(&mut symbols, label, value).map_err(error)?;
+        }
+        match statement {
+            Some(Statement::Constant(name, expression)) => constants.push((line, name, expression)),
+            Some(statement) => {
+                address += statement.size();
+                statements.push((line, statement));
+            }
+            None => (),
+        }
+    }
+    for (line, name, expression) in constants {
+        let error = |kind| AssemblyError { line, kind };
+        let value = expression.evaluate(&symbols).map_err(error)?;
+        define(&mut symbols, name, value).map_err(error)?;
+    }
+    let mut code = Vec::with_capacity(address);
+    for (line, statement) in statements {
+        let error = |kind| AssemblyError { line, kind };
+        statement.encode(&symbols, &mut code).map_err(error)?;
+    }
+    Ok(Intcode::from(code))
+}
+
+fn define<V>(
+    symbols: &mut HashMap<String, V>,
+    name: &str,
+    value: V,
+) -> Result<(), AssemblyErrorKind> {
+    if symbols.contains_key(name) {
+        return Err(AssemblyErrorKind::DuplicateSymbol(name.to_string()));
+    }
+    symbols.insert(name.to_string(), value);
+    Ok(())
+}
+
+enum Statement<'a> {
+    Instruction(OpCode, Vec<(ParameterMode, Expression<'a>)>),
+    Data(Vec<Expression<'a>>),
+    Constant(&'a str, Expression<'a>),
+}
+
+impl<'a> Statement<'a> {
+    fn size(&self) -> usize {
+        match self {
+            Statement::Instruction(opcode, _) => opcode.arity() + 1,
+            Statement::Data(values) => values.len(),
+            Statement::Constant(_, _) => 0,
+        }
+    }
+
+    fn encode<V: Value>(
+        &self,
+        symbols: &HashMap<String, V>,
+        code: &mut Vec<V>,
+    ) -> Result<(), AssemblyErrorKind> {
+        match self {
+            Statement::Instruction(opcode, operands) => {
+                let instruction = operands
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (mode, _))| mode.digit() * 10i64.pow(i as u32 + 2))
+                    .sum::<i64>()
+                    + opcode.code();
+                code.push(V::from(instruction as i32));
+                for (_, expression) in operands {
+                    code.push(expression.evaluate(symbols)?);
+                }
+            }
+            Statement::Data(values) => {
+                for expression in values {
+                    code.push(expression.evaluate(symbols)?);
+                }
+            }
+            Statement::Constant(_, _) => (),
+        }
+        Ok(())
+    }
+}
+
+fn parse_line(text: &str) -> Result<(Option<&str>, Option<Statement<'_>>), AssemblyErrorKind> {
+    let text = text.split(';').next().unwrap_or("").trim();
+    let (label, text) = match text.find(':') {
+        Some(i) => (Some(parse_symbol(text[..i].trim())?), text[i + 1..].trim()),
+        None => (None, text),
+    };
+    if text.is_empty() {
+        return Ok((label, None));
+    }
+    let (keyword, rest) = match text.find(char::is_whitespace) {
+        Some(i) => (&text[..i], text[i..].trim()),
+        None => (text, ""),
+    };
+    let arguments: Vec<&str> = match rest {
+        "" => Vec::new(),
+        _ => rest.split(',').map(str::trim).collect(),
+    };
+    let statement = match keyword {
+        ".data" => Statement::Data(
+            arguments
+                .into_iter()
+                .map(Expression::parse)
+                .collect::<Result<_, _>>()?,
+        ),
+        ".const" => {
+            let mut definition = rest.splitn(2, '=');
+            let name = parse_symbol(definition.next().unwrap_or("").trim())?;
+            let expression = definition
+                .next()
+                .ok_or_else(|| AssemblyErrorKind::InvalidExpression(rest.to_string()))?;
+            Statement::Constant(name, Expression::parse(expression)?)
+        }
+        directive if directive.starts_with('.') => {
+            return Err(AssemblyErrorKind::UnknownDirective(directive.to_string()))
+        }
+        mnemonic => parse_instruction(mnemonic, arguments)?,
+    };
+    Ok((label, Some(statement)))
+}
+
+fn parse_instruction<'a>(
+    mnemonic: &str,
+    arguments: Vec<&'a str>,
+) -> Result<Statement<'a>, AssemblyErrorKind> {
+    let opcode = OpCode::from_mnemonic(mnemonic)
+        .ok_or_else(|| AssemblyErrorKind::UnknownMnemonic(mnemonic.to_string()))?;
+    if arguments.len() != opcode.arity() {
+        return Err(AssemblyErrorKind::WrongOperandCount {
+            expected: opcode.arity(),
+            found: arguments.len(),
+        });
+    }
+    let operands: Vec<(ParameterMode, Expression)> = arguments
+        .into_iter()
+        .map(parse_operand)
+        .collect::<Result<_, _>>()?;
+    if let Some(written) = opcode.written_parameter() {
+        if operands[written].0 == ParameterMode::Immediate {
+            return Err(AssemblyErrorKind::WriteInImmediateMode);
+        }
+    }
+    Ok(Statement::Instruction(opcode, operands))
+}
+
+fn parse_operand(operand: &str) -> Result<(ParameterMode, Expression<'_>), AssemblyErrorKind> {
+    if let Some(expression) = operand.strip_prefix('#') {
+        Ok((ParameterMode::Immediate, Expression::parse(expression)?))
+    } else if operand.starts_with('[') && operand.ends_with(']') {
+        let expression = &operand[1..operand.len() - 1];
+        Ok((ParameterMode::Position, Expression::parse(expression)?))
+    } else if operand == "rb" {
+        Ok((ParameterMode::Relative, Expression::parse("0")?))
+    } else if operand.starts_with("rb+") || operand.starts_with("rb-") {
+        Ok((ParameterMode::Relative, Expression::parse(&operand[2..])?))
+    } else {
+        Err(AssemblyErrorKind::InvalidOperand(operand.to_string()))
+    }
+}
+
+fn parse_symbol(name: &str) -> Result<&str, AssemblyErrorKind> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "rb";
+    match valid {
+        true => Ok(name),
+        false => Err(AssemblyErrorKind::InvalidSymbol(name.to_string())),
+    }
+}
+
+/// A sum of signed terms, each term being a number or a symbol.
+struct Expression<'a> {
+    terms: Vec<(bool, &'a str)>,
+}
+
+impl<'a> Expression<'a> {
+    fn parse(text: &'a str) -> Result<Self, AssemblyErrorKind> {
+        let invalid = || AssemblyErrorKind::InvalidExpression(text.trim().to_string());
+        let mut terms = Vec::new();
+        let mut negative = false;
+        let mut start = 0;
+        for (i, c) in text.char_indices() {
+            if c == '+' || c == '-' {
+                let term = text[start..i].trim();
+                match term.is_empty() {
+                    true if terms.is_empty() && start == 0 => (),
+                    true => return Err(invalid()),
+                    false => terms.push((negative, term)),
+                }
+                negative = c == '-';
+                start = i + 1;
+            }
+        }
+        let term = text[start..].trim();
+        if term.is_empty() {
+            return Err(invalid());
+        }
+        terms.push((negative, term));
+        Ok(Expression { terms })
+    }
+
+    fn evaluate<V: Value>(&self, symbols: &HashMap<String, V>) -> Result<V, AssemblyErrorKind> {
+        let mut result = V::from(0);
+        for (negative, term) in &self.terms {
+            let value = match term.parse::<V>() {
+                Ok(value) => value,
+                Err(_) => match symbols.get(*term) {
+                    Some(value) => value.clone(),
+                    None => {
+                        parse_symbol(term)?;
+                        return Err(AssemblyErrorKind::UndefinedSymbol(term.to_string()));
+                    }
+                },
+            };
+            result = match negative {
+                true => result + V::from(-1) * value,
+                false => result + value,
+            };
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod assembler_should {
+    use super::*;
+    use crate::intcode::disassembler::disassemble;
+    use crate::intcode::{Pipe, Program, ProgramStatus};
+
+    fn error(source: &str) -> AssemblyError {
+        assemble::<i64>(source).unwrap_err()
+    }
+
+    #[test]
+    fn encode_instructions_in_every_parameter_mode() {
+        let result = assemble(
+            "add [9], #10, [3]
+             mul rb+3, rb-2, rb
+             hlt",
+        );
+
+        assert_eq!(
+            result,
+            Ok(Intcode::from(vec![1001, 9, 10, 3, 22202, 3, -2, 0, 99]))
+        );
+    }
+
+    #[test]
+    fn resolve_labels_and_constants() {
+        let result = assemble(
+            "; reads a value and outputs it plus TEN
+             start:  in [value]
+                     add [value], #TEN, [value]
+                     out [value]
+                     jnz #1, #end
+             value:  .data 0
+             end:    hlt
+             .const TEN = 5 + 5",
+        );
+
+        assert_eq!(
+            result,
+            Ok(Intcode::from(vec![
+                3, 11, 1001, 11, 10, 11, 4, 11, 1105, 1, 12, 0, 99
+            ]))
+        );
+    }
+
+    #[test]
+    fn evaluate_expressions_with_labels_and_offsets() {
+        let result = assemble(
+            "        out [table + 1]
+                     out #end - table
+             table:  .data 7, 8, -3
+             end:",
+        );
+
+        assert_eq!(result, Ok(Intcode::from(vec![4, 5, 104, 3, 7, 8, -3])));
+    }
+
+    #[test]
+    fn assemble_the_statements_of_a_disassembled_program() {
+        let intcode = Intcode::from(vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ]);
+        let source: Vec<String> = disassemble(&intcode)
+            .lines
+            .iter()
+            .map(|l| l.statement.to_string())
+            .collect();
+
+        let result = assemble(&source.join("\n"));
+
+        assert_eq!(result, Ok(intcode));
+    }
+
+    #[test]
+    fn assemble_a_runnable_program() {
+        let intcode = assemble(
+            "loop:   in [counter]
+                     jz [counter], #end
+                     out #LETTER
+                     jnz #1, #loop
+             end:    hlt
+             counter: .data 0
+             .const LETTER = 65",
+        )
+        .unwrap();
+        let mut program: Program = Program::new(intcode);
+        let output = Pipe::new();
+        program.set_output(&output);
+        program.write(1);
+        program.write(1);
+        program.write(0);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(65));
+        assert_eq!(output.read(), Some(65));
+        assert_eq!(output.read(), None);
+    }
+
+    #[test]
+    fn report_an_unknown_mnemonic_with_its_line() {
+        let result = error("in [0]\n\n  jmp #0");
+
+        assert_eq!(
+            result,
+            AssemblyError {
+                line: 3,
+                kind: AssemblyErrorKind::UnknownMnemonic("jmp".to_string()),
+            }
+        );
+        assert_eq!(result.to_string(), "line 3: unknown mnemonic 'jmp'");
+    }
+
+    #[test]
+    fn report_a_wrong_operand_count() {
+        let result = error("add [1], [2]");
+
+        assert_eq!(
+            result.kind,
+            AssemblyErrorKind::WrongOperandCount {
+                expected: 3,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn report_an_invalid_operand() {
+        let result = error("out 12");
+
+        assert_eq!(
+            result.kind,
+            AssemblyErrorKind::InvalidOperand("12".to_string())
+        );
+    }
+
+    #[test]
+    fn report_an_undefined_symbol() {
+        let result = error("hlt\nout [missing]");
+
+        assert_eq!(
+            result,
+            AssemblyError {
+                line: 2,
+                kind: AssemblyErrorKind::UndefinedSymbol("missing".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn report_a_duplicate_symbol() {
+        let result = error("here: hlt\nhere: hlt");
+
+        assert_eq!(
+            result,
+            AssemblyError {
+                line: 2,
+                kind: AssemblyErrorKind::DuplicateSymbol("here".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn report_a_write_in_immediate_mode() {
+        let result = error("in #3");
+
+        assert_eq!(result.kind, AssemblyErrorKind::WriteInImmediateMode);
+    }
+
+    #[test]
+    fn report_an_invalid_expression() {
+        let result = error("out #1 +");
+
+        assert_eq!(
+            result.kind,
+            AssemblyErrorKind::InvalidExpression("1 +".to_string())
+        );
+    }
+
+    #[test]
+    fn report_an_unknown_directive() {
+        let result = error(".fill 3");
+
+        assert_eq!(
+            result.kind,
+            AssemblyErrorKind::UnknownDirective(".fill".to_string())
+        );
+    }
+}
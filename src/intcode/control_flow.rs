@@ -0,0 +1,363 @@
+use super::disassembler::{disassemble_at, ListingLine, Operand, Statement};
+use super::trace::{ExecutionObserver, InstructionEvent};
+use super::{Intcode, Memory, OpCode, ParameterMode, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// The control-flow graph of an intcode program: its basic blocks, from the first address, linked
+/// by the jumps between them.
+///
+/// Jumps to a target in position or relative mode cannot be followed without running the program.
+/// Their blocks are marked as ending with an indirect jump, and the graph can be refined with the
+/// targets recorded by `JumpTargets` during a run.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ControlFlowGraph<V = i64> {
+    pub blocks: BTreeMap<usize, BasicBlock<V>>,
+}
+
+/// A sequence of instructions always executed one after the other, entered at its first one.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BasicBlock<V = i64> {
+    pub start: usize,
+    pub lines: Vec<ListingLine<V>>,
+    pub successors: Vec<Edge>,
+    /// Whether the block ends with a jump whose target is only known at run time.
+    pub indirect_jump: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Edge {
+    pub target: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EdgeKind {
+    /// To the next instruction.
+    FallThrough,
+    /// To the target of a jump in immediate mode.
+    Jump,
+    /// To a target of an indirect jump, recorded during a run.
+    Observed,
+}
+
+/// An observer recording the targets of the indirect jumps taken by a program.
+#[derive(Clone, Default, Debug)]
+pub struct JumpTargets {
+    targets: BTreeMap<usize, BTreeSet<usize>>,
+}
+
+impl JumpTargets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The recorded targets of the jump at the given address.
+    pub fn get(&self, address: usize) -> impl Iterator<Item = usize> + '_ {
+        self.targets.get(&address).into_iter().flatten().cloned()
+    }
+}
+
+impl<V: Value> ExecutionObserver<V> for JumpTargets {
+    fn on_instruction(&mut self, event: &InstructionEvent<V>) {
+        let taken = match event.instruction.opcode() {
+            OpCode::JumpIfTrue => event.operands[0] != V::from(0),
+            OpCode::JumpIfFalse => event.operands[0] == V::from(0),
+            _ => return,
+        };
+        let indirect = event.instruction.get_parameter_mode(1) != &ParameterMode::Immediate;
+        if let (true, true, Some(target)) = (taken, indirect, event.operands[1].to_address()) {
+            self.targets
+                .entry(event.address)
+                .or_default()
+                .insert(target);
+        }
+    }
+}
+
+impl<V: Value> ControlFlowGraph<V> {
+    /// Builds the graph of the loaded program, following the jumps known without running it.
+    pub fn build<M: Memory<V>>(intcode: &Intcode<V, M>) -> Self {
+        Self::build_refined(intcode, &JumpTargets::new())
+    }
+
+    /// Builds the graph of the loaded program, following the indirect jumps to the targets
+    /// recorded during a run as well.
+    pub fn build_refined<M: Memory<V>>(intcode: &Intcode<V, M>, observed: &JumpTargets) -> Self {
+        let mut lines = BTreeMap::new();
+        let mut successors = BTreeMap::new();
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        let mut pending = vec![0];
+        while let Some(address) = pending.pop() {
+            if lines.contains_key(&address) {
+                continue;
+            }
+            let line = disassemble_at(intcode, address);
+            let (edges, indirect) = line_successors(&line, observed);
+            let ends_block = is_terminator(&line);
+            for edge in &edges {
+                if ends_block {
+                    leaders.insert(edge.target);
+                }
+                pending.push(edge.target);
+            }
+            successors.insert(address, (edges, indirect));
+            lines.insert(address, line);
+        }
+        let mut blocks: BTreeMap<usize, BasicBlock<V>> = BTreeMap::new();
+        let mut current: Option<BasicBlock<V>> = None;
+        for (address, line) in lines {
+            let continues = match &current {
+                Some(block) => {
+                    let last = block.lines.last().unwrap();
+                    !leaders.contains(&address)
+                        && !is_terminator(last)
+                        && last.address + last.words.len() == address
+                }
+                None => false,
+            };
+            if !continues {
+                if let Some(block) = current.take() {
+                    blocks.insert(block.start, block);
+                }
+                current = Some(BasicBlock {
+                    start: address,
+                    lines: Vec::new(),
+                    successors: Vec::new(),
+                    indirect_jump: false,
+                });
+            }
+            let block = current.as_mut().unwrap();
+            let (edges, indirect) = successors.remove(&address).unwrap();
+            block.lines.push(line);
+            block.successors = edges;
+            block.indirect_jump = indirect;
+        }
+        if let Some(block) = current {
+            blocks.insert(block.start, block);
+        }
+        ControlFlowGraph { blocks }
+    }
+
+    /// The graph in the DOT language of Graphviz. Indirect jumps lead to a `?` node, and the
+    /// targets recorded for them are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph intcode {{").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+        for block in self.blocks.values() {
+            let label: String = block
+                .lines
+                .iter()
+                .map(|l| format!("{}: {}\\l", l.address, l.statement))
+                .collect();
+            writeln!(dot, "    b{} [label=\"{}\"];", block.start, label).unwrap();
+            for edge in &block.successors {
+                let attributes = match edge.kind {
+                    EdgeKind::FallThrough => "",
+                    EdgeKind::Jump => " [label=\"jump\"]",
+                    EdgeKind::Observed => " [label=\"observed\", style=dashed]",
+                };
+                writeln!(
+                    dot,
+                    "    b{} -> b{}{};",
+                    block.start, edge.target, attributes
+                )
+                .unwrap();
+            }
+            if block.indirect_jump {
+                writeln!(dot, "    u{} [label=\"?\", shape=circle];", block.start).unwrap();
+                writeln!(
+                    dot,
+                    "    b{} -> u{} [label=\"indirect\", style=dotted];",
+                    block.start, block.start
+                )
+                .unwrap();
+            }
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+/// Whether execution never goes on to the next instruction after this one.
+fn is_terminator<V: Value>(line: &ListingLine<V>) -> bool {
+    match &line.statement {
+        Statement::Instruction(opcode, _) => matches!(
+            opcode,
+            OpCode::JumpIfTrue | OpCode::JumpIfFalse | OpCode::Halt
+        ),
+        Statement::Data(_) => true,
+    }
+}
+
+/// The instructions executed after the one of the line, and whether it jumps to a target only
+/// known at run time.
+fn line_successors<V: Value>(line: &ListingLine<V>, observed: &JumpTargets) -> (Vec<Edge>, bool) {
+    let next = Edge {
+        target: line.address + line.words.len(),
+        kind: EdgeKind::FallThrough,
+    };
+    let (opcode, operands) = match &line.statement {
+        Statement::Instruction(opcode, operands) => (opcode, operands),
+        Statement::Data(_) => return (Vec::new(), false),
+    };
+    let jumps_if = match opcode {
+        OpCode::JumpIfTrue => true,
+        OpCode::JumpIfFalse => false,
+        OpCode::Halt => return (Vec::new(), false),
+        _ => return (vec![next], false),
+    };
+    let (may_jump, may_continue) = match &operands[0] {
+        Operand {
+            mode: ParameterMode::Immediate,
+            value,
+        } => {
+            let taken = (*value != V::from(0)) == jumps_if;
+            (taken, !taken)
+        }
+        _ => (true, true),
+    };
+    let mut edges = Vec::new();
+    if may_continue {
+        edges.push(next);
+    }
+    if !may_jump {
+        return (edges, false);
+    }
+    match &operands[1] {
+        Operand {
+            mode: ParameterMode::Immediate,
+            value,
+        } => {
+            if let Some(target) = value.to_address() {
+                edges.push(Edge {
+                    target,
+                    kind: EdgeKind::Jump,
+                });
+            }
+            (edges, false)
+        }
+        _ => {
+            edges.extend(observed.get(line.address).map(|target| Edge {
+                target,
+                kind: EdgeKind::Observed,
+            }));
+            (edges, true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod control_flow_graph_should {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+    use crate::intcode::Program;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn starts(graph: &ControlFlowGraph) -> Vec<usize> {
+        graph.blocks.keys().cloned().collect()
+    }
+
+    fn edge(target: usize, kind: EdgeKind) -> Edge {
+        Edge { target, kind }
+    }
+
+    #[test]
+    fn split_the_program_in_blocks_at_jumps_and_their_targets() {
+        let intcode: Intcode = assemble(
+            "       in [n]
+             loop:  add [n], #-1, [n]
+                    jnz [n], #loop
+                    out [n]
+                    hlt
+             n:     .data 0",
+        )
+        .unwrap();
+
+        let graph = ControlFlowGraph::build(&intcode);
+
+        assert_eq!(starts(&graph), vec![0, 2, 9]);
+        assert_eq!(
+            graph.blocks[&0].successors,
+            vec![edge(2, EdgeKind::FallThrough)]
+        );
+        assert_eq!(
+            graph.blocks[&2].successors,
+            vec![edge(9, EdgeKind::FallThrough), edge(2, EdgeKind::Jump)]
+        );
+        assert_eq!(graph.blocks[&9].lines.len(), 2);
+        assert!(graph.blocks[&9].successors.is_empty());
+    }
+
+    #[test]
+    fn follow_only_the_jumps_always_taken_with_an_immediate_condition() {
+        let intcode: Intcode = assemble(
+            "       jnz #1, #end
+                    .data 12
+             end:   hlt",
+        )
+        .unwrap();
+
+        let graph = ControlFlowGraph::build(&intcode);
+
+        assert_eq!(starts(&graph), vec![0, 4]);
+        assert_eq!(graph.blocks[&0].successors, vec![edge(4, EdgeKind::Jump)]);
+    }
+
+    #[test]
+    fn mark_the_indirect_jumps_and_refine_them_with_a_run() {
+        let intcode: Intcode = assemble(
+            "       jz #0, [target]
+                    hlt
+             end:   out #7
+                    hlt
+             target: .data end",
+        )
+        .unwrap();
+        let graph = ControlFlowGraph::build(&intcode);
+        let mut program = Program::new(intcode.clone());
+        let targets = Rc::new(RefCell::new(JumpTargets::new()));
+        program.add_observer(Box::new(targets.clone()));
+        program.run().unwrap();
+
+        let refined = ControlFlowGraph::build_refined(&intcode, &targets.borrow());
+
+        assert_eq!(starts(&graph), vec![0]);
+        assert!(graph.blocks[&0].indirect_jump);
+        assert!(graph.blocks[&0].successors.is_empty());
+        assert_eq!(starts(&refined), vec![0, 4]);
+        assert_eq!(
+            refined.blocks[&0].successors,
+            vec![edge(4, EdgeKind::Observed)]
+        );
+        assert!(refined.blocks[&0].indirect_jump);
+    }
+
+    #[test]
+    fn export_the_graph_to_dot() {
+        let intcode: Intcode = assemble(
+            "       jnz [flag], [target]
+                    hlt
+             flag:  .data 1
+             target: .data 0",
+        )
+        .unwrap();
+
+        let dot = ControlFlowGraph::build(&intcode).to_dot();
+
+        assert_eq!(
+            dot,
+            "digraph intcode {\n    \
+             node [shape=box, fontname=\"monospace\"];\n    \
+             b0 [label=\"0: jnz [4], [5]\\l\"];\n    \
+             b0 -> b3;\n    \
+             u0 [label=\"?\", shape=circle];\n    \
+             b0 -> u0 [label=\"indirect\", style=dotted];\n    \
+             b3 [label=\"3: hlt\\l\"];\n\
+             }\n"
+        );
+    }
+}
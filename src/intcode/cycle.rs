@@ -0,0 +1,145 @@
+use super::{Instruction, Memory, OpCode, ProgramState, Value};
+
+/// Detects a program going back to a state it was already in, which it will then repeat forever.
+///
+/// It follows Brent's algorithm: the state is saved after a number of steps doubling each time,
+/// and every following state is compared to it, so that a cycle is found within a few times its
+/// length without keeping every state. Reading or writing a value starts over, since the program
+/// may then behave differently. The instructions of custom opcodes are treated as reading and
+/// writing values, as they may do it or be nondeterministic. Halting leaves the state unchanged
+/// without being a loop.
+#[derive(Clone)]
+pub(super) struct CycleDetector<V, M> {
+    saved: Option<ProgramState<V, M>>,
+    steps: usize,
+    period: usize,
+}
+
+impl<V: Value, M: Memory<V>> CycleDetector<V, M> {
+    pub(super) fn new() -> Self {
+        CycleDetector {
+            saved: None,
+            steps: 0,
+            period: 1,
+        }
+    }
+
+    /// Records the state of a program about to execute an instruction. Returns whether the
+    /// program was already in it since the last input or output, in which case the instruction
+    /// would start the loop again.
+    pub(super) fn is_repeated(
+        &mut self,
+        instruction: &Instruction,
+        state: &ProgramState<V, M>,
+    ) -> bool {
+        if let OpCode::Input | OpCode::Output | OpCode::Custom(_) = instruction.opcode {
+            self.saved = None;
+            return false;
+        }
+        if let OpCode::Halt = instruction.opcode {
+            return false;
+        }
+        match &self.saved {
+            Some(saved) if is_same_state(saved, state) => return true,
+            Some(_) if self.steps < self.period => self.steps += 1,
+            Some(_) => {
+                self.saved = Some(state.clone());
+                self.steps = 1;
+                self.period *= 2;
+            }
+            None => {
+                self.saved = Some(state.clone());
+                self.steps = 1;
+                self.period = 1;
+            }
+        }
+        false
+    }
+}
+
+fn is_same_state<V: Value, M: Memory<V>>(
+    saved: &ProgramState<V, M>,
+    state: &ProgramState<V, M>,
+) -> bool {
+    saved.current_position == state.current_position
+        && saved.relative_base == state.relative_base
+        && saved.intcode == state.intcode
+}
+
+#[cfg(test)]
+mod cycle_detector_should {
+    use crate::intcode::{Intcode, IntcodeErrorKind, Program, ProgramStatus};
+
+    #[test]
+    fn report_a_program_jumping_to_itself() {
+        let mut program = Program::new(Intcode::from(vec![1105, 1, 0]));
+        program.set_cycle_detection(true);
+
+        let error = program.run().unwrap_err();
+
+        assert_eq!(error.kind, IntcodeErrorKind::InfiniteLoop);
+        assert_eq!(error.instruction_pointer, 0);
+        assert_eq!(program.status(), ProgramStatus::Faulted);
+    }
+
+    #[test]
+    fn report_the_instruction_that_would_start_the_loop_again() {
+        let mut program = Program::new(Intcode::from(vec![1101, 0, 0, 9, 1105, 1, 0, 99, 0, 0]));
+        program.set_cycle_detection(true);
+
+        let error = program.run().unwrap_err();
+
+        assert_eq!(error.kind, IntcodeErrorKind::InfiniteLoop);
+        assert_eq!(error.instruction_pointer, 4);
+        assert_eq!(error.instruction, 1105);
+        assert_eq!(program.instruction_count(), 3);
+    }
+
+    #[test]
+    fn report_a_loop_going_back_to_the_same_memory() {
+        // Counts from 0 to 2 in the cell at 18, over and over.
+        let mut program = Program::new(Intcode::from(vec![
+            1001, 18, 1, 18, 1008, 18, 2, 19, 1006, 19, 0, 1101, 0, 0, 18, 1105, 1, 0, 0, 0,
+        ]));
+        program.set_cycle_detection(true);
+
+        let result = program.run();
+
+        assert_eq!(result.unwrap_err().kind, IntcodeErrorKind::InfiniteLoop);
+    }
+
+    #[test]
+    fn not_report_a_loop_counting_until_it_ends() {
+        let mut program = Program::new(Intcode::from(vec![
+            1001, 12, 1, 12, 1007, 12, 1000, 13, 1005, 13, 0, 99, 0, 0,
+        ]));
+        program.set_cycle_detection(true);
+
+        assert!(program.run().unwrap());
+
+        assert_eq!(program.intcode().read(12), 1000);
+    }
+
+    #[test]
+    fn not_report_a_program_halting() {
+        let mut program = Program::new(Intcode::from(vec![1101, 0, 0, 5, 99, 0]));
+        program.set_cycle_detection(true);
+
+        assert!(program.run().unwrap());
+
+        assert_eq!(program.status(), ProgramStatus::Over);
+    }
+
+    #[test]
+    fn not_report_a_loop_reading_its_input() {
+        let mut program = Program::new(Intcode::from(vec![3, 7, 1105, 1, 0, 99]));
+        program.set_cycle_detection(true);
+        for value in 0..10 {
+            program.write(value);
+        }
+
+        assert!(!program.run().unwrap());
+
+        assert_eq!(program.status(), ProgramStatus::Waiting);
+    }
+}
@@ -0,0 +1,774 @@
+use super::disassembler::{disassemble_at_with, ListingLine};
+use super::{
+    InstructionSet, IntcodeError, Memory, OpCode, PersistentMemory, Program, ProgramStatus, Value,
+};
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+/// A debugger stepping through a program, stopping on breakpoints.
+pub struct Debugger<V = i64, M = PersistentMemory<V>> {
+    program: Program<V, M>,
+    breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Breakpoint {
+    Address(usize),
+    OpCode(OpCode),
+}
+
+/// The reason why the debugger handed control back.
+#[derive(Clone, PartialEq, Debug)]
+pub enum StopReason<V = i64> {
+    Stepped,
+    Breakpoint(Breakpoint),
+    Waiting,
+    Over,
+    Faulted(IntcodeError<V>),
+}
+
+impl<V: Value, M: Memory<V>> Debugger<V, M> {
+    pub fn new(program: Program<V, M>) -> Self {
+        Debugger {
+            program,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn program(&self) -> &Program<V, M> {
+        &self.program
+    }
+
+    pub fn into_program(self) -> Program<V, M> {
+        self.program
+    }
+
+    /// Adds a breakpoint. Returns `false` if it was already set.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+        if self.breakpoints.contains(&breakpoint) {
+            return false;
+        }
+        self.breakpoints.push(breakpoint);
+        true
+    }
+
+    /// Removes a breakpoint. Returns `false` if it was not set.
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|b| b != breakpoint);
+        self.breakpoints.len() != count
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Executes a single instruction, unless the program is over or faulted. As with `run`, the
+    /// outputs then go to the sink of the program, and a program waiting for an input takes one
+    /// from its source.
+    pub fn step(&mut self) -> StopReason<V> {
+        if self.program.state.status == ProgramStatus::Waiting {
+            self.program.exchange();
+        }
+        match self.program.state.status {
+            ProgramStatus::Over | ProgramStatus::Faulted => (),
+            _ => {
+                self.program.execute();
+                self.program.exchange();
+            }
+        }
+        match self.program.state.status {
+            ProgramStatus::Running | ProgramStatus::BudgetExhausted => StopReason::Stepped,
+            ProgramStatus::Waiting => StopReason::Waiting,
+            ProgramStatus::Over => StopReason::Over,
+            ProgramStatus::Faulted => StopReason::Faulted(self.program.error.clone().unwrap()),
+        }
+    }
+
+    /// Goes back before the last instructions executed, as far as the history of the program
+    /// goes. Returns the number of instructions undone.
+    pub fn step_back(&mut self, count: usize) -> Result<usize, IntcodeError<V>> {
+        self.program.step_back(count)
+    }
+
+    /// Goes back before the last instruction writing to an address, if it is in the history of
+    /// the program.
+    pub fn rewind_to_last_write(&mut self, address: usize) -> Result<bool, IntcodeError<V>> {
+        self.program.rewind_to_last_write(address)
+    }
+
+    /// Goes back before the instruction writing an output, counted from 0, if it is in the
+    /// history of the program.
+    pub fn rewind_to_output(&mut self, output: usize) -> Result<bool, IntcodeError<V>> {
+        self.program.rewind_to_output(output)
+    }
+
+    /// Executes instructions until the next one hits a breakpoint, or until the program needs
+    /// input, is over or faults.
+    pub fn continue_execution(&mut self) -> StopReason<V> {
+        let mut reason = self.step();
+        while reason == StopReason::Stepped {
+            if let Some(breakpoint) = self.hit_breakpoint() {
+                return StopReason::Breakpoint(breakpoint);
+            }
+            reason = self.step();
+        }
+        reason
+    }
+
+    fn hit_breakpoint(&self) -> Option<Breakpoint> {
+        let address = self.instruction_pointer();
+        let opcode = self
+            .program
+            .instructions
+            .decode(&self.program.state.intcode.read(address))
+            .ok()
+            .map(|i| i.opcode);
+        self.breakpoints
+            .iter()
+            .find(|b| match b {
+                Breakpoint::Address(a) => *a == address,
+                Breakpoint::OpCode(o) => Some(o) == opcode.as_ref(),
+            })
+            .cloned()
+    }
+
+    pub fn instruction_pointer(&self) -> usize {
+        self.program.state.current_position
+    }
+
+    pub fn relative_base(&self) -> V {
+        self.program.state.relative_base.clone()
+    }
+
+    pub fn status(&self) -> ProgramStatus {
+        self.program.state.status.clone()
+    }
+
+    /// The decoded instruction at the instruction pointer.
+    pub fn current_instruction(&self) -> ListingLine<V> {
+        self.disassemble_at(self.instruction_pointer())
+    }
+
+    pub fn disassemble_at(&self, address: usize) -> ListingLine<V> {
+        disassemble_at_with(
+            &self.program.state.intcode,
+            &self.program.instructions,
+            address,
+        )
+    }
+
+    pub fn read_memory(&self, address: usize) -> V {
+        self.program.state.intcode.read(address)
+    }
+
+    pub fn write_memory(&mut self, address: usize, value: V) {
+        self.program.state.intcode.write(address, value);
+        self.program.record_patch();
+    }
+
+    pub fn write_input(&self, value: V) {
+        self.program.write(value);
+    }
+
+    pub fn input_contents(&self) -> Vec<V> {
+        self.program.input.contents()
+    }
+
+    pub fn output_contents(&self) -> Option<Vec<V>> {
+        self.program.output.as_ref().map(|o| o.contents())
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::Address(address) => write!(f, "address {}", address),
+            Breakpoint::OpCode(opcode) => write!(f, "opcode {}", opcode.mnemonic()),
+        }
+    }
+}
+
+impl<V: Value> Display for StopReason<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            StopReason::Stepped => write!(f, "stepped"),
+            StopReason::Breakpoint(breakpoint) => write!(f, "breakpoint on {}", breakpoint),
+            StopReason::Waiting => write!(f, "waiting for input"),
+            StopReason::Over => write!(f, "program is over"),
+            StopReason::Faulted(error) => write!(f, "program faulted: {}", error),
+        }
+    }
+}
+
+const HELP: &str = "\
+s, step [count]          execute instructions
+c, continue              run until a breakpoint, an input is needed or the program ends
+back [count]             undo instructions, as far as the history goes
+lastwrite <addr>         go back before the last instruction writing to an address
+producer <index>         go back before the instruction writing an output, counted from 0
+b, break <addr|mnemonic> add a breakpoint on an address or an opcode
+d, delete <addr|mnemonic> remove a breakpoint
+breakpoints              list breakpoints
+i, info                  show the registers and the current instruction
+l, list [addr] [count]   disassemble statements, from the current instruction by default
+m, mem <addr> [count]    print memory
+set <addr> <value>       patch memory
+in <value>[,<value>...]  write values to the input
+pipes                    show the contents of the input and output pipes
+save <file>              save the program, to resume it later with `debug <file>`
+h, help                  show this help
+q, quit                  leave the debugger";
+
+/// Runs an interactive debugging session, reading commands from `input` until it ends or a
+/// `quit` command is read.
+pub fn run_repl<V: Value, M: Memory<V>, R: BufRead, W: Write>(
+    debugger: &mut Debugger<V, M>,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    writeln!(output, "{}", debugger.current_instruction())?;
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["q"] | ["quit"] => return Ok(()),
+            [] => (),
+            [command, arguments @ ..] => {
+                if let Err(message) = execute_command(debugger, command, arguments, &mut output)? {
+                    writeln!(output, "{}", message)?;
+                }
+            }
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn execute_command<V: Value, M: Memory<V>, W: Write>(
+    debugger: &mut Debugger<V, M>,
+    command: &str,
+    arguments: &[&str],
+    output: &mut W,
+) -> io::Result<Result<(), String>> {
+    match (command, arguments) {
+        ("s", _) | ("step", _) => {
+            let count = match arguments.first() {
+                Some(count) => match count.parse::<usize>() {
+                    Ok(count) => count,
+                    Err(_) => return Ok(Err(format!("Invalid count: {}", count))),
+                },
+                None => 1,
+            };
+            let mut reason = StopReason::Stepped;
+            for _ in 0..count {
+                reason = debugger.step();
+                if reason != StopReason::Stepped {
+                    break;
+                }
+            }
+            print_stop(debugger, &reason, output)?;
+        }
+        ("c", []) | ("continue", []) => {
+            let reason = debugger.continue_execution();
+            print_stop(debugger, &reason, output)?;
+        }
+        ("back", _) if arguments.len() <= 1 => {
+            let count = match parse_numbers(arguments) {
+                Ok(numbers) => numbers.first().cloned().unwrap_or(1),
+                Err(message) => return Ok(Err(message)),
+            };
+            let undone = match debugger.step_back(count) {
+                Ok(undone) => undone,
+                Err(error) => return Ok(Err(format!("Cannot go back: {}", error))),
+            };
+            if undone < count {
+                writeln!(
+                    output,
+                    "Went back {} instructions, to the start of the history",
+                    undone
+                )?;
+            }
+            writeln!(output, "{}", debugger.current_instruction())?;
+        }
+        ("lastwrite", [address]) => match address
+            .parse::<usize>()
+            .map(|a| (a, debugger.rewind_to_last_write(a)))
+        {
+            Ok((_, Ok(true))) => writeln!(output, "{}", debugger.current_instruction())?,
+            Ok((address, Ok(false))) => {
+                return Ok(Err(format!("No write to {} in the history", address)))
+            }
+            Ok((_, Err(error))) => return Ok(Err(format!("Cannot go back: {}", error))),
+            Err(_) => return Ok(Err(format!("Invalid address: {}", address))),
+        },
+        ("producer", [index]) => match index
+            .parse::<usize>()
+            .map(|i| (i, debugger.rewind_to_output(i)))
+        {
+            Ok((_, Ok(true))) => writeln!(output, "{}", debugger.current_instruction())?,
+            Ok((index, Ok(false))) => {
+                return Ok(Err(format!("No output {} in the history", index)))
+            }
+            Ok((_, Err(error))) => return Ok(Err(format!("Cannot go back: {}", error))),
+            Err(_) => return Ok(Err(format!("Invalid index: {}", index))),
+        },
+        ("b", [breakpoint]) | ("break", [breakpoint]) => {
+            match parse_breakpoint(breakpoint, debugger.program.instruction_set()) {
+                Ok(breakpoint) => {
+                    let message = match debugger.add_breakpoint(breakpoint.clone()) {
+                        true => "Breakpoint added on",
+                        false => "Breakpoint already set on",
+                    };
+                    writeln!(output, "{} {}", message, breakpoint)?;
+                }
+                Err(message) => return Ok(Err(message)),
+            }
+        }
+        ("d", [breakpoint]) | ("delete", [breakpoint]) => {
+            match parse_breakpoint(breakpoint, debugger.program.instruction_set()) {
+                Ok(breakpoint) => {
+                    let message = match debugger.remove_breakpoint(&breakpoint) {
+                        true => "Breakpoint removed on",
+                        false => "No breakpoint on",
+                    };
+                    writeln!(output, "{} {}", message, breakpoint)?;
+                }
+                Err(message) => return Ok(Err(message)),
+            }
+        }
+        ("breakpoints", []) => {
+            for breakpoint in debugger.breakpoints() {
+                writeln!(output, "{}", breakpoint)?;
+            }
+        }
+        ("i", []) | ("info", []) => {
+            writeln!(
+                output,
+                "ip={} rb={} status={:?}",
+                debugger.instruction_pointer(),
+                debugger.relative_base(),
+                debugger.status()
+            )?;
+            writeln!(output, "{}", debugger.current_instruction())?;
+        }
+        ("l", _) | ("list", _) if arguments.len() <= 2 => {
+            let (address, count) = match parse_numbers(arguments) {
+                Ok(numbers) => (
+                    numbers
+                        .first()
+                        .cloned()
+                        .unwrap_or_else(|| debugger.instruction_pointer()),
+                    numbers.get(1).cloned().unwrap_or(10),
+                ),
+                Err(message) => return Ok(Err(message)),
+            };
+            let mut address = Some(address);
+            for _ in 0..count {
+                let line = match address {
+                    Some(address) => debugger.disassemble_at(address),
+                    None => return Ok(Err("Cannot list past the last address".to_string())),
+                };
+                address = line.address.checked_add(line.words.len());
+                writeln!(output, "{}", line)?;
+            }
+        }
+        ("m", _) | ("mem", _) if !arguments.is_empty() && arguments.len() <= 2 => {
+            let (address, count) = match parse_numbers(arguments) {
+                Ok(numbers) => (numbers[0], numbers.get(1).cloned().unwrap_or(1)),
+                Err(message) => return Ok(Err(message)),
+            };
+            if count > 0 && address.checked_add(count - 1).is_none() {
+                return Ok(Err("Cannot read past the last address".to_string()));
+            }
+            let values: Vec<String> = (0..count)
+                .map(|offset| debugger.read_memory(address + offset).to_string())
+                .collect();
+            writeln!(output, "{:>6}: {}", address, values.join(","))?;
+        }
+        ("set", [address, value]) => match (address.parse::<usize>(), value.parse::<V>()) {
+            (Ok(address), Ok(value)) => debugger.write_memory(address, value),
+            _ => {
+                return Ok(Err(format!(
+                    "Invalid address or value: {} {}",
+                    address, value
+                )))
+            }
+        },
+        ("in", _) if !arguments.is_empty() => {
+            let values: Result<Vec<V>, _> = arguments
+                .join("")
+                .split(',')
+                .map(|v| v.trim().parse::<V>())
+                .collect();
+            match values {
+                Ok(values) => values.into_iter().for_each(|v| debugger.write_input(v)),
+                Err(_) => return Ok(Err(format!("Invalid values: {}", arguments.join(" ")))),
+            }
+        }
+        ("pipes", []) => {
+            writeln!(output, "input:  {}", join(&debugger.input_contents()))?;
+            match debugger.output_contents() {
+                Some(values) => writeln!(output, "output: {}", join(&values))?,
+                None => writeln!(output, "output: not connected")?,
+            }
+        }
+        ("save", [file]) => {
+            if let Err(e) = fs::write(file, debugger.program().save()) {
+                return Ok(Err(format!("Cannot save to {}: {}", file, e)));
+            }
+            writeln!(output, "Saved to {}", file)?;
+        }
+        ("h", []) | ("help", []) => writeln!(output, "{}", HELP)?,
+        _ => return Ok(Err(format!("Unknown command: {}", command))),
+    }
+    Ok(Ok(()))
+}
+
+fn print_stop<V: Value, M: Memory<V>, W: Write>(
+    debugger: &Debugger<V, M>,
+    reason: &StopReason<V>,
+    output: &mut W,
+) -> io::Result<()> {
+    if *reason != StopReason::Stepped {
+        writeln!(output, "Stopped: {}", reason)?;
+    }
+    writeln!(output, "{}", debugger.current_instruction())
+}
+
+fn parse_breakpoint<V: Value>(
+    text: &str,
+    instructions: &InstructionSet<V>,
+) -> Result<Breakpoint, String> {
+    match text.parse::<usize>() {
+        Ok(address) => Ok(Breakpoint::Address(address)),
+        Err(_) => instructions
+            .from_mnemonic(text)
+            .map(Breakpoint::OpCode)
+            .ok_or_else(|| format!("Invalid breakpoint: {}", text)),
+    }
+}
+
+fn parse_numbers(arguments: &[&str]) -> Result<Vec<usize>, String> {
+    arguments
+        .iter()
+        .map(|a| {
+            a.parse::<usize>()
+                .map_err(|_| format!("Invalid number: {}", a))
+        })
+        .collect()
+}
+
+fn join<V: Value>(values: &[V]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    values.join(",")
+}
+
+#[cfg(test)]
+mod debugger_should {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+    use crate::intcode::disassembler::Statement;
+    use crate::intcode::io::{FixedInput, VecOutput};
+    use crate::intcode::{squares, HistoryLimits, IntcodeErrorKind, Pipe};
+
+    fn debugger(source: &str) -> Debugger {
+        let mut program = Program::new(assemble(source).unwrap());
+        program.set_output(&Pipe::new());
+        Debugger::new(program)
+    }
+
+    const COUNTDOWN: &str = "
+        loop: out [counter]
+              add [counter], #-1, [counter]
+              jnz [counter], #loop
+              hlt
+        counter: .data 3";
+
+    #[test]
+    fn execute_a_single_instruction_on_step() {
+        let mut debugger = debugger(COUNTDOWN);
+
+        let result = debugger.step();
+
+        assert_eq!(result, StopReason::Stepped);
+        assert_eq!(debugger.instruction_pointer(), 2);
+        assert_eq!(debugger.output_contents(), Some(vec![3]));
+    }
+
+    #[test]
+    fn exchange_with_the_source_and_the_sink_of_the_program() {
+        let mut program = Program::new(squares());
+        program.set_input_source(Some(Box::new(FixedInput::new(vec![2, 3]))));
+        let output = VecOutput::new();
+        program.set_output_sink(Some(Box::new(output.clone())));
+        let mut debugger = Debugger::new(program);
+
+        let result = debugger.continue_execution();
+
+        assert_eq!(result, StopReason::Waiting);
+        assert_eq!(output.values(), vec![4, 9]);
+        assert_eq!(debugger.output_contents(), Some(vec![]));
+    }
+
+    #[test]
+    fn stop_on_an_address_breakpoint() {
+        let mut debugger = debugger(COUNTDOWN);
+        debugger.add_breakpoint(Breakpoint::Address(6));
+
+        let first = debugger.continue_execution();
+        let second = debugger.continue_execution();
+
+        assert_eq!(first, StopReason::Breakpoint(Breakpoint::Address(6)));
+        assert_eq!(second, StopReason::Breakpoint(Breakpoint::Address(6)));
+        assert_eq!(debugger.output_contents(), Some(vec![3, 2]));
+        assert_eq!(debugger.read_memory(10), 1);
+    }
+
+    #[test]
+    fn stop_on_an_opcode_breakpoint() {
+        let mut debugger = debugger(COUNTDOWN);
+        debugger.add_breakpoint(Breakpoint::OpCode(OpCode::Halt));
+
+        let result = debugger.continue_execution();
+
+        assert_eq!(
+            result,
+            StopReason::Breakpoint(Breakpoint::OpCode(OpCode::Halt))
+        );
+        assert_eq!(debugger.output_contents(), Some(vec![3, 2, 1]));
+        assert_eq!(debugger.current_instruction().statement.to_string(), "hlt");
+    }
+
+    #[test]
+    fn continue_to_the_end_after_removing_breakpoints() {
+        let mut debugger = debugger(COUNTDOWN);
+        debugger.add_breakpoint(Breakpoint::Address(2));
+        debugger.continue_execution();
+
+        let removed = debugger.remove_breakpoint(&Breakpoint::Address(2));
+        let result = debugger.continue_execution();
+
+        assert!(removed);
+        assert_eq!(result, StopReason::Over);
+        assert_eq!(debugger.status(), ProgramStatus::Over);
+    }
+
+    #[test]
+    fn not_add_the_same_breakpoint_twice() {
+        let mut debugger = debugger(COUNTDOWN);
+
+        let first = debugger.add_breakpoint(Breakpoint::Address(2));
+        let second = debugger.add_breakpoint(Breakpoint::Address(2));
+
+        assert!(first);
+        assert!(!second);
+        assert_eq!(debugger.breakpoints(), &[Breakpoint::Address(2)]);
+    }
+
+    #[test]
+    fn stop_when_waiting_for_input_and_resume_once_given() {
+        let mut debugger = debugger("in [5]\nout [5]\nhlt");
+
+        let waiting = debugger.continue_execution();
+        debugger.write_input(42);
+        let input = debugger.input_contents();
+        let over = debugger.continue_execution();
+
+        assert_eq!(waiting, StopReason::Waiting);
+        assert_eq!(input, vec![42]);
+        assert_eq!(over, StopReason::Over);
+        assert_eq!(debugger.output_contents(), Some(vec![42]));
+    }
+
+    #[test]
+    fn patch_memory() {
+        let mut debugger = debugger(COUNTDOWN);
+        debugger.write_memory(10, 1);
+
+        let result = debugger.continue_execution();
+
+        assert_eq!(result, StopReason::Over);
+        assert_eq!(debugger.output_contents(), Some(vec![1]));
+    }
+
+    #[test]
+    fn go_back_to_the_instruction_writing_an_output() {
+        let mut debugger = debugger(COUNTDOWN);
+        debugger.program.set_history(Some(HistoryLimits::default()));
+        debugger.continue_execution();
+
+        let found = debugger.rewind_to_output(1);
+        let undone = debugger.step_back(1);
+
+        assert_eq!(found, Ok(true));
+        assert_eq!(undone, Ok(1));
+        assert_eq!(debugger.instruction_pointer(), 6);
+        assert_eq!(debugger.read_memory(10), 2);
+        assert_eq!(debugger.output_contents(), Some(vec![3]));
+        assert_eq!(debugger.status(), ProgramStatus::Running);
+    }
+
+    #[test]
+    fn go_back_over_a_patch_of_the_memory() {
+        let mut debugger = debugger(COUNTDOWN);
+        debugger.program.set_history(Some(HistoryLimits::default()));
+        for _ in 0..3 {
+            debugger.step();
+        }
+        debugger.write_memory(10, 1);
+        debugger.step();
+        debugger.step();
+
+        let after_patch = debugger.step_back(1);
+        let (position, counter) = (debugger.instruction_pointer(), debugger.read_memory(10));
+        let before_patch = debugger.step_back(2);
+
+        assert_eq!(after_patch, Ok(1));
+        assert_eq!((position, counter), (2, 1));
+        assert_eq!(before_patch, Ok(2));
+        assert_eq!(debugger.instruction_pointer(), 6);
+        assert_eq!(debugger.read_memory(10), 2);
+    }
+
+    #[test]
+    fn report_a_fault() {
+        let mut debugger = debugger(".data 42");
+
+        let result = debugger.step();
+
+        assert_eq!(
+            result,
+            StopReason::Faulted(IntcodeError {
+                kind: IntcodeErrorKind::UnknownOpcode,
+                instruction_pointer: 0,
+                instruction: 42,
+            })
+        );
+        assert_eq!(
+            debugger.current_instruction().statement,
+            Statement::Data(42)
+        );
+    }
+}
+
+#[cfg(test)]
+mod repl_should {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+    use crate::intcode::io::{FixedInput, VecOutput};
+    use crate::intcode::{squares, Pipe};
+
+    fn run(source: &str, commands: &str) -> String {
+        let mut program: Program = Program::new(assemble(source).unwrap());
+        program.set_output(&Pipe::new());
+        run_program(program, commands)
+    }
+
+    fn run_program(program: Program, commands: &str) -> String {
+        let mut debugger = Debugger::new(program);
+        let mut output = Vec::new();
+        run_repl(&mut debugger, commands.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn step_and_show_the_current_instruction() {
+        let result = run("in [4]\nhlt", "in 7\ns\nq\n");
+
+        assert_eq!(
+            result,
+            "     0: in [4]                           ; 3,4\n\
+             > > \
+             \x20    2: hlt                              ; 99\n\
+             > "
+        );
+    }
+
+    #[test]
+    fn stop_on_breakpoints_and_show_pipes() {
+        let result = run("out #1\nout #2\nhlt", "b out\nc\nc\npipes\nmem 0 2\nq\n");
+
+        assert_eq!(
+            result,
+            "     0: out #1                           ; 104,1\n\
+             > Breakpoint added on opcode out\n\
+             > Stopped: breakpoint on opcode out\n\
+             \x20    2: out #2                           ; 104,2\n\
+             > Stopped: program is over\n\
+             \x20    4: hlt                              ; 99\n\
+             > input:  \n\
+             output: 1,2\n\
+             > \x20    0: 104,1\n\
+             > "
+        );
+    }
+
+    #[test]
+    fn report_ranges_past_the_last_address() {
+        let last = usize::MAX.to_string();
+        let commands = format!("mem {} 2\nlist {} 2\nq\n", last, last);
+
+        let result = run("hlt", &commands);
+
+        assert!(result.contains("Cannot read past the last address"));
+        assert!(result.contains("Cannot list past the last address"));
+    }
+
+    #[test]
+    fn read_and_list_the_last_address() {
+        let last = usize::MAX.to_string();
+        let commands = format!("mem {} 1\nlist {} 1\nq\n", last, last);
+
+        let result = run("hlt", &commands);
+
+        assert!(result.contains(&format!("{}: 0\n", last)));
+        assert!(!result.contains("Cannot"));
+    }
+
+    #[test]
+    fn read_and_list_past_the_end_of_the_memory() {
+        let result = run("hlt", "mem 5 3\nlist 5 1\nq\n");
+
+        assert_eq!(
+            result,
+            "     0: hlt                              ; 99\n\
+             >      5: 0,0,0\n\
+             > \x20    5: .data 0                          ; 0\n\
+             > "
+        );
+    }
+
+    #[test]
+    fn step_and_continue_with_the_source_and_the_sink_of_the_program() {
+        let mut program = Program::new(squares());
+        program.set_input_source(Some(Box::new(FixedInput::new(vec![2, 3]))));
+        let output = VecOutput::new();
+        program.set_output_sink(Some(Box::new(output.clone())));
+
+        let result = run_program(program, "s 10\nc\npipes\nq\n");
+
+        assert_eq!(
+            result,
+            "     0: in [15]                          ; 3,15\n\
+             > \x20    9: out [16]                         ; 4,16\n\
+             > Stopped: waiting for input\n\
+             \x20    0: in [15]                          ; 3,15\n\
+             > input:  \n\
+             output: \n\
+             > "
+        );
+        assert_eq!(output.values(), vec![4, 9]);
+    }
+
+    #[test]
+    fn report_unknown_commands() {
+        let result = run("hlt", "jump\nq\n");
+
+        assert!(result.contains("Unknown command: jump"));
+    }
+}
@@ -0,0 +1,241 @@
+use super::{Instruction, InstructionSet, Intcode, Memory, OpCode, ParameterMode, Value};
+use std::fmt::{self, Display, Formatter};
+
+/// The listing of a disassembled intcode program.
+pub struct Listing<V = i64> {
+    pub lines: Vec<ListingLine<V>>,
+}
+
+/// A line of a listing, with the address and the raw words of its statement.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ListingLine<V = i64> {
+    pub address: usize,
+    pub words: Vec<V>,
+    pub statement: Statement<V>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Statement<V = i64> {
+    Instruction(OpCode, Vec<Operand<V>>),
+    Data(V),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Operand<V = i64> {
+    pub mode: ParameterMode,
+    pub value: V,
+}
+
+/// Disassembles the loaded program, with a linear sweep from its first address. Words that do not
+/// decode to a valid instruction are listed as data.
+pub fn disassemble<V: Value, M: Memory<V>>(intcode: &Intcode<V, M>) -> Listing<V> {
+    disassemble_with(intcode, &InstructionSet::new())
+}
+
+/// Disassembles the loaded program, decoding the opcodes of the given instruction set.
+pub fn disassemble_with<V: Value, M: Memory<V>>(
+    intcode: &Intcode<V, M>,
+    instructions: &InstructionSet<V>,
+) -> Listing<V> {
+    let mut lines = Vec::new();
+    let mut address = 0;
+    while address < intcode.len() {
+        let line = decode_line(intcode, instructions, address, intcode.len());
+        address += line.words.len();
+        lines.push(line);
+    }
+    Listing { lines }
+}
+
+/// Disassembles the single statement at the given address.
+pub fn disassemble_at<V: Value, M: Memory<V>>(
+    intcode: &Intcode<V, M>,
+    address: usize,
+) -> ListingLine<V> {
+    disassemble_at_with(intcode, &InstructionSet::new(), address)
+}
+
+/// Disassembles the single statement at the given address, decoding the opcodes of the given
+/// instruction set.
+pub fn disassemble_at_with<V: Value, M: Memory<V>>(
+    intcode: &Intcode<V, M>,
+    instructions: &InstructionSet<V>,
+    address: usize,
+) -> ListingLine<V> {
+    decode_line(intcode, instructions, address, usize::MAX)
+}
+
+fn decode_line<V: Value, M: Memory<V>>(
+    intcode: &Intcode<V, M>,
+    instructions: &InstructionSet<V>,
+    address: usize,
+    end: usize,
+) -> ListingLine<V> {
+    let word = intcode.read(address);
+    match instructions.decode(&word) {
+        Ok(instruction) if is_decodable(&instruction, address, end) => {
+            let arity = instruction.opcode.arity();
+            let operands = (0..arity)
+                .map(|i| Operand {
+                    mode: *instruction.get_parameter_mode(i),
+                    value: intcode.read(address + 1 + i),
+                })
+                .collect();
+            ListingLine {
+                address,
+                words: (0..=arity).map(|i| intcode.read(address + i)).collect(),
+                statement: Statement::Instruction(instruction.opcode, operands),
+            }
+        }
+        _ => ListingLine {
+            address,
+            words: vec![word.clone()],
+            statement: Statement::Data(word),
+        },
+    }
+}
+
+fn is_decodable(instruction: &Instruction, address: usize, end: usize) -> bool {
+    let arity = instruction.opcode.arity();
+    let writes_in_immediate_mode = instruction
+        .opcode
+        .written_parameter()
+        .map(|p| instruction.get_parameter_mode(p) == &ParameterMode::Immediate)
+        .unwrap_or(false);
+    instruction.modes_count <= arity && !writes_in_immediate_mode && end - address > arity
+}
+
+impl<V: Value> Display for Listing<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: Value> Display for ListingLine<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let words: Vec<String> = self.words.iter().map(|w| w.to_string()).collect();
+        write!(
+            f,
+            "{:>6}: {:<32} ; {}",
+            self.address,
+            self.statement.to_string(),
+            words.join(",")
+        )
+    }
+}
+
+impl<V: Value> Display for Statement<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Statement::Instruction(opcode, operands) => {
+                write!(f, "{}", opcode.mnemonic())?;
+                for (i, operand) in operands.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", separator, operand)?;
+                }
+                Ok(())
+            }
+            Statement::Data(value) => write!(f, ".data {}", value),
+        }
+    }
+}
+
+impl<V: Value> Display for Operand<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.mode {
+            ParameterMode::Position => write!(f, "[{}]", self.value),
+            ParameterMode::Immediate => write!(f, "#{}", self.value),
+            ParameterMode::Relative if self.value < V::from(0) => write!(f, "rb{}", self.value),
+            ParameterMode::Relative => write!(f, "rb+{}", self.value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod disassembler_should {
+    use super::*;
+
+    fn statements(code: Vec<i64>) -> Vec<String> {
+        disassemble(&Intcode::from(code))
+            .lines
+            .iter()
+            .map(|l| l.statement.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn decode_instructions_with_their_operands() {
+        let result = statements(vec![1, 9, 10, 3, 2, 3, 11, 0, 99]);
+
+        assert_eq!(
+            result,
+            vec!["add [9], [10], [3]", "mul [3], [11], [0]", "hlt"]
+        );
+    }
+
+    #[test]
+    fn decode_operands_in_every_parameter_mode() {
+        let result = statements(vec![1001, 12, 5, 3, 22201, 3, -2, 0]);
+
+        assert_eq!(result, vec!["add [12], #5, [3]", "add rb+3, rb-2, rb+0"]);
+    }
+
+    #[test]
+    fn list_words_that_do_not_decode_as_data() {
+        let result = statements(vec![3, 9, 42, 1301, 11101, 0, 0]);
+
+        assert_eq!(
+            result,
+            vec![
+                "in [9]",
+                ".data 42",
+                ".data 1301",
+                ".data 11101",
+                ".data 0",
+                ".data 0"
+            ]
+        );
+    }
+
+    #[test]
+    fn list_a_truncated_instruction_as_data() {
+        let result = statements(vec![99, 1105, 1]);
+
+        assert_eq!(result, vec!["hlt", ".data 1105", ".data 1"]);
+    }
+
+    #[test]
+    fn list_lines_with_their_address_and_raw_words() {
+        let listing = disassemble(&Intcode::from(vec![1105, 1, 7, 99]));
+
+        assert_eq!(
+            listing.to_string(),
+            "     0: jnz #1, #7                       ; 1105,1,7\n     3: hlt                              ; 99\n"
+        );
+    }
+
+    #[test]
+    fn disassemble_a_single_instruction_at_an_address() {
+        let intcode = Intcode::from(vec![99, 204, -1]);
+
+        let result = disassemble_at(&intcode, 1);
+
+        assert_eq!(
+            result,
+            ListingLine {
+                address: 1,
+                words: vec![204, -1],
+                statement: Statement::Instruction(
+                    OpCode::Output,
+                    vec![Operand {
+                        mode: ParameterMode::Relative,
+                        value: -1
+                    }]
+                ),
+            }
+        );
+    }
+}
@@ -0,0 +1,437 @@
+use super::{
+    CycleDetector, Instruction, IntcodeError, Memory, OpCode, Pipe, Program, ProgramState,
+    ProgramStatus, Value,
+};
+use std::collections::VecDeque;
+
+/// How much of its execution a program records to go back in time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HistoryLimits {
+    /// The number of instructions that can be undone. Up to `checkpoint_interval` more may be
+    /// kept, as the oldest ones are forgotten a checkpoint at a time.
+    pub capacity: usize,
+    /// The number of instructions between two copies of the state of the program. Going back
+    /// executes again the instructions since the closest copy, so a short interval makes it
+    /// faster, at the cost of more copies of the memory. Copies are nearly free with the default
+    /// `PersistentMemory`.
+    pub checkpoint_interval: usize,
+}
+
+impl Default for HistoryLimits {
+    fn default() -> Self {
+        HistoryLimits {
+            capacity: 100_000,
+            checkpoint_interval: 1_000,
+        }
+    }
+}
+
+/// The last instructions executed by a program, from the checkpoints of its state before some of
+/// them. A state between two checkpoints is computed again by replaying the recorded inputs from
+/// the checkpoint before it.
+///
+/// Custom instructions are not replayed, as their executor may not give the same result twice: a
+/// checkpoint is saved after each of them.
+#[derive(Clone)]
+pub(super) struct History<V, M> {
+    limits: HistoryLimits,
+    /// The number of the oldest recorded step, counting the instructions executed since the
+    /// history was enabled. A checkpoint is always kept before it.
+    first: usize,
+    steps: VecDeque<Step<V>>,
+    /// The states before some of the steps, by step number.
+    checkpoints: VecDeque<(usize, ProgramState<V, M>)>,
+    /// The number of values output since the history was enabled.
+    outputs: usize,
+}
+
+/// An instruction executed by the program.
+#[derive(Clone)]
+pub(super) struct Step<V> {
+    write: Option<usize>,
+    inputs: Vec<V>,
+    outputs: Vec<V>,
+    /// The number of values output before this step.
+    output_number: usize,
+    opcode: OpCode,
+    /// For custom instructions, the contents of the input and the length of the output before
+    /// their execution, to find out what they read and wrote.
+    pipes: Option<(Vec<V>, usize)>,
+}
+
+impl<V: Value, M: Memory<V>> History<V, M> {
+    fn new(limits: HistoryLimits) -> Self {
+        assert!(
+            limits.checkpoint_interval > 0,
+            "The checkpoint interval must not be 0"
+        );
+        History {
+            limits,
+            first: 0,
+            steps: VecDeque::new(),
+            checkpoints: VecDeque::new(),
+            outputs: 0,
+        }
+    }
+
+    /// Forgets the recorded steps, for a program whose state was replaced.
+    pub(super) fn clear(&mut self) {
+        *self = History::new(self.limits);
+    }
+
+    /// The number of the next step.
+    fn current(&self) -> usize {
+        self.first + self.steps.len()
+    }
+
+    fn save_checkpoint(&mut self, number: usize, state: &ProgramState<V, M>) {
+        if self.checkpoints.back().map(|(n, _)| *n) != Some(number) {
+            self.checkpoints.push_back((number, state.clone()));
+        }
+    }
+
+    /// Records a state changed outside of the execution of the program, replacing the checkpoint
+    /// before the next step, so that going back after the change replays from it.
+    fn patch(&mut self, state: &ProgramState<V, M>) {
+        let number = self.current();
+        match self.checkpoints.back_mut() {
+            Some((last, saved)) if *last == number => *saved = state.clone(),
+            _ => self.checkpoints.push_back((number, state.clone())),
+        }
+    }
+
+    /// Starts recording the execution of an instruction by the program in the given state.
+    pub(super) fn start_step(
+        &mut self,
+        instruction: &Instruction,
+        state: &ProgramState<V, M>,
+        input: &Pipe<V>,
+        output: Option<&Pipe<V>>,
+    ) -> Step<V> {
+        let number = self.current();
+        let due = match self.checkpoints.back() {
+            Some((last, _)) => number - last >= self.limits.checkpoint_interval,
+            None => true,
+        };
+        if due {
+            self.save_checkpoint(number, state);
+        }
+        let opcode = instruction.opcode.clone();
+        let write = opcode.written_parameter().and_then(|i| {
+            state
+                .parameter_address(i, instruction.get_parameter_mode(i))
+                .ok()
+        });
+        let outputs = match opcode {
+            OpCode::Output => state
+                .read_parameter(0, instruction.get_parameter_mode(0))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        };
+        let pipes = match opcode {
+            OpCode::Custom(_) => Some((input.contents(), output.map_or(0, |o| o.contents().len()))),
+            _ => None,
+        };
+        Step {
+            write,
+            inputs: Vec::new(),
+            outputs,
+            output_number: self.outputs,
+            opcode,
+            pipes,
+        }
+    }
+
+    /// Records an instruction executed, leaving the program in the given state.
+    pub(super) fn end_step(
+        &mut self,
+        mut step: Step<V>,
+        state: &ProgramState<V, M>,
+        input: &Pipe<V>,
+        output: Option<&Pipe<V>>,
+    ) {
+        match (&step.opcode, step.pipes.take()) {
+            (OpCode::Input, _) => {
+                step.inputs = step
+                    .write
+                    .map(|a| state.intcode.read(a))
+                    .into_iter()
+                    .collect()
+            }
+            (OpCode::Custom(_), Some((mut inputs, outputs))) => {
+                inputs.truncate(inputs.len().saturating_sub(input.contents().len()));
+                step.inputs = inputs;
+                step.outputs = output
+                    .map(|o| o.contents().into_iter().skip(outputs).collect())
+                    .unwrap_or_default();
+            }
+            _ => (),
+        }
+        self.outputs += step.outputs.len();
+        let custom = matches!(step.opcode, OpCode::Custom(_));
+        self.steps.push_back(step);
+        if custom {
+            self.save_checkpoint(self.current(), state);
+        }
+        self.forget_oldest_steps();
+    }
+
+    /// Forgets the steps before the second checkpoint, as long as enough steps are left.
+    fn forget_oldest_steps(&mut self) {
+        while let Some((second, _)) = self.checkpoints.get(1) {
+            if self.current() - second < self.limits.capacity {
+                break;
+            }
+            let forgotten = second - self.first;
+            self.first = *second;
+            self.steps.drain(..forgotten);
+            self.checkpoints.pop_front();
+        }
+    }
+
+    fn step(&self, number: usize) -> &Step<V> {
+        &self.steps[number - self.first]
+    }
+
+    /// The number of the last step matching the predicate.
+    fn find_last<P: Fn(&Step<V>) -> bool>(&self, predicate: P) -> Option<usize> {
+        self.steps
+            .iter()
+            .rposition(predicate)
+            .map(|i| self.first + i)
+    }
+}
+
+impl<V: Value, M: Memory<V>> Program<V, M> {
+    /// Records the execution of the program from now on, so that it can be taken back to the
+    /// state before any of the last instructions executed. `None` disables the recording and
+    /// forgets the instructions recorded so far.
+    ///
+    /// Going back gives the values read from the input back to it, and takes the values written
+    /// to the output out of it, unless they were already read by another program.
+    pub fn set_history(&mut self, limits: Option<HistoryLimits>) {
+        self.history = limits.map(History::new);
+    }
+
+    /// The number of instructions that can be undone.
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, |h| h.steps.len())
+    }
+
+    /// Takes the program back to the state before the last `count` instructions executed, or
+    /// as far back as its history goes. Returns the number of instructions undone.
+    pub fn step_back(&mut self, count: usize) -> Result<usize, IntcodeError<V>> {
+        let count = count.min(self.history_len());
+        if count > 0 {
+            let current = self.history.as_ref().unwrap().current();
+            self.rewind_to(current - count)?;
+        }
+        Ok(count)
+    }
+
+    /// Takes the program back to the state before the last recorded instruction writing to the
+    /// given address, which becomes the current instruction. Returns `false` if there is none.
+    pub fn rewind_to_last_write(&mut self, address: usize) -> Result<bool, IntcodeError<V>> {
+        let number = self
+            .history
+            .as_ref()
+            .and_then(|h| h.find_last(|s| s.write == Some(address)));
+        if let Some(number) = number {
+            self.rewind_to(number)?;
+        }
+        Ok(number.is_some())
+    }
+
+    /// Takes the program back to the state before the instruction writing an output, which
+    /// becomes the current instruction. Outputs are counted from 0 since the history was enabled.
+    /// Returns `false` if the instruction is not recorded anymore, or if there was no such
+    /// output yet.
+    pub fn rewind_to_output(&mut self, output: usize) -> Result<bool, IntcodeError<V>> {
+        let number = self.history.as_ref().and_then(|h| {
+            h.find_last(|s| s.output_number <= output && output < s.output_number + s.outputs.len())
+        });
+        if let Some(number) = number {
+            self.rewind_to(number)?;
+        }
+        Ok(number.is_some())
+    }
+
+    /// Records a change of the state made outside of the execution of the program, such as a
+    /// write to its memory by the debugger, so that going back after it keeps it.
+    pub(super) fn record_patch(&mut self) {
+        if let Some(history) = &mut self.history {
+            history.patch(&self.state);
+        }
+    }
+
+    /// Takes the program back to the state before a recorded step. Fails if an instruction
+    /// replayed from the checkpoint before it fails, leaving the program unchanged.
+    fn rewind_to(&mut self, number: usize) -> Result<(), IntcodeError<V>> {
+        let history = self.history.as_ref().unwrap();
+        let (checkpoint, state) = history
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|(n, _)| *n <= number)
+            .unwrap();
+        let replayed_input = Pipe::new();
+        for number in *checkpoint..number {
+            for value in &history.step(number).inputs {
+                replayed_input.write(value.clone());
+            }
+        }
+        let replayed_output = Pipe::new();
+        let mut state = state.clone();
+        for _ in *checkpoint..number {
+            let replayed = state
+                .next_instruction(&self.instructions)
+                .and_then(|instruction| {
+                    state.execute(
+                        &instruction,
+                        &self.instructions,
+                        &replayed_input,
+                        Some(&replayed_output),
+                    )
+                });
+            if let Err(kind) = replayed {
+                return Err(IntcodeError {
+                    kind,
+                    instruction_pointer: state.current_position,
+                    instruction: state.intcode.read(state.current_position),
+                });
+            }
+        }
+        let history = self.history.as_mut().unwrap();
+        history.outputs = history.step(number).output_number;
+        let undone: Vec<Step<V>> = history.steps.drain(number - history.first..).collect();
+        while history.checkpoints.back().unwrap().0 > number {
+            history.checkpoints.pop_back();
+        }
+        self.state = state;
+        self.state.status = ProgramStatus::Running;
+        self.error = None;
+        if self.cycle_detector.is_some() {
+            self.cycle_detector = Some(CycleDetector::new());
+        }
+        let mut inputs: Vec<V> = undone.iter().flat_map(|s| s.inputs.clone()).collect();
+        if !inputs.is_empty() {
+            inputs.extend(self.input.contents());
+            self.input.set_contents(inputs);
+        }
+        let outputs: usize = undone.iter().map(|s| s.outputs.len()).sum();
+        if let (Some(output), true) = (&self.output, outputs > 0) {
+            let mut contents = output.contents();
+            contents.truncate(contents.len().saturating_sub(outputs));
+            output.set_contents(contents);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod history_should {
+    use crate::intcode::{squares, HistoryLimits, Pipe, Program};
+
+    fn recording(inputs: &[i64], checkpoint_interval: usize) -> Program {
+        let mut program = Program::new(squares());
+        program.set_output(&Pipe::new());
+        program.set_history(Some(HistoryLimits {
+            capacity: 100,
+            checkpoint_interval,
+        }));
+        for input in inputs {
+            program.write(*input);
+        }
+        program
+    }
+
+    fn memory(program: &Program) -> Vec<i64> {
+        let intcode = program.intcode();
+        (0..intcode.len()).map(|p| intcode.read(p)).collect()
+    }
+
+    #[test]
+    fn step_back_to_the_state_before_each_instruction() {
+        for steps in 0..=13 {
+            let mut program = recording(&[2, 3, 0], 4);
+            program.run().unwrap();
+            let mut expected = recording(&[2, 3, 0], 4);
+            expected.set_step_budget(Some(steps));
+            expected.run().unwrap();
+
+            let undone = program.step_back(13 - steps).unwrap();
+
+            assert_eq!(undone, 13 - steps);
+            assert_eq!(
+                program.state.current_position,
+                expected.state.current_position
+            );
+            assert_eq!(program.history_len(), steps);
+            assert_eq!(memory(&program), memory(&expected));
+            assert_eq!(program.input().contents(), expected.input().contents());
+            assert_eq!(
+                program.output().unwrap().contents(),
+                expected.output().unwrap().contents()
+            );
+        }
+    }
+
+    #[test]
+    fn run_again_after_stepping_back() {
+        let mut program = recording(&[2, 3, 0], 1);
+        program.run().unwrap();
+
+        program.step_back(5).unwrap();
+        let over = program.run().unwrap();
+
+        assert!(over);
+        assert_eq!(program.output().unwrap().contents(), vec![4, 9]);
+        assert_eq!(program.history_len(), 13);
+    }
+
+    #[test]
+    fn rewind_to_the_last_write_of_a_cell() {
+        let mut program = recording(&[2, 3, 0], 1_000);
+        program.run().unwrap();
+
+        let found = program.rewind_to_last_write(16).unwrap();
+
+        assert!(found);
+        assert_eq!(program.state.current_position, 5);
+        assert_eq!(program.intcode().read(16), 4);
+        assert_eq!(program.intcode().read(15), 3);
+        assert_eq!(program.input().contents(), vec![0]);
+        assert!(!program.rewind_to_last_write(20).unwrap());
+    }
+
+    #[test]
+    fn rewind_to_the_instruction_writing_an_output() {
+        let mut program = recording(&[2, 3, 0], 3);
+        program.run().unwrap();
+
+        let found = program.rewind_to_output(1).unwrap();
+
+        assert!(found);
+        assert_eq!(program.state.current_position, 9);
+        assert_eq!(program.intcode().read(16), 9);
+        assert_eq!(program.output().unwrap().contents(), vec![4]);
+        assert!(!program.rewind_to_output(1).unwrap());
+    }
+
+    #[test]
+    fn forget_the_oldest_instructions_beyond_its_capacity() {
+        let mut program = recording(&[1, 2, 3, 4, 5, 0], 2);
+        program.set_history(Some(HistoryLimits {
+            capacity: 5,
+            checkpoint_interval: 2,
+        }));
+        program.run().unwrap();
+
+        let undone = program.step_back(100).unwrap();
+
+        assert!((5..=6).contains(&undone));
+        assert_eq!(program.history_len(), 0);
+        assert_eq!(program.step_back(1), Ok(0));
+    }
+}
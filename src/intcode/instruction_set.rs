@@ -0,0 +1,459 @@
+use super::{
+    Instruction, IntcodeErrorKind, Memory, OpCode, ParameterMode, Pipe, ProgramState,
+    ProgramStatus, Value, WriteError, MAX_ARITY,
+};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The definition of an opcode added to an instruction set.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub struct CustomOpCode {
+    /// The two last digits of the instruction code.
+    pub code: i64,
+    pub mnemonic: &'static str,
+    /// The number of parameters of the instruction.
+    pub arity: usize,
+    /// The index of the parameter the instruction writes to, if any. It is only used to decode
+    /// and trace the instruction.
+    pub written_parameter: Option<usize>,
+}
+
+/// Executes the instructions of a custom opcode.
+pub trait InstructionExecutor<V> {
+    fn execute(&self, context: &mut InstructionContext<V>) -> Result<(), IntcodeErrorKind<V>>;
+}
+
+impl<V, F> InstructionExecutor<V> for F
+where
+    F: Fn(&mut InstructionContext<V>) -> Result<(), IntcodeErrorKind<V>>,
+{
+    fn execute(&self, context: &mut InstructionContext<V>) -> Result<(), IntcodeErrorKind<V>> {
+        self(context)
+    }
+}
+
+/// The opcodes understood by a program: the standard ones, and the custom opcodes registered on
+/// top of them. A custom opcode registered with the code of a standard one overrides it.
+///
+/// Clones share the executors, so that a set can be given to several programs.
+#[derive(Clone)]
+pub struct InstructionSet<V = i64> {
+    custom: HashMap<i64, (CustomOpCode, Rc<dyn InstructionExecutor<V>>)>,
+}
+
+impl<V: Value> InstructionSet<V> {
+    /// Creates a set of the standard opcodes.
+    pub fn new() -> Self {
+        InstructionSet {
+            custom: HashMap::new(),
+        }
+    }
+
+    /// Adds an opcode, or overrides an existing one. Returns the custom opcode it replaces, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the code does not fit in two digits, if the arity exceeds the number of
+    /// parameter modes an instruction can hold, or if the written parameter is not one of the
+    /// parameters.
+    pub fn register<E: InstructionExecutor<V> + 'static>(
+        &mut self,
+        opcode: CustomOpCode,
+        executor: E,
+    ) -> Option<CustomOpCode> {
+        assert!(
+            (0..100).contains(&opcode.code),
+            "opcode {} does not fit in two digits",
+            opcode.code
+        );
+        assert!(
+            opcode.arity <= MAX_ARITY,
+            "opcode {} has more than {} parameters",
+            opcode.mnemonic,
+            MAX_ARITY
+        );
+        assert!(
+            !matches!(opcode.written_parameter, Some(p) if p >= opcode.arity),
+            "opcode {} writes to a parameter it does not have",
+            opcode.mnemonic
+        );
+        self.custom
+            .insert(opcode.code, (opcode, Rc::new(executor)))
+            .map(|(replaced, _)| replaced)
+    }
+
+    /// Whether the set only holds the standard opcodes.
+    pub fn is_standard(&self) -> bool {
+        self.custom.is_empty()
+    }
+
+    /// The opcode with the given code, if there is one.
+    pub fn opcode(&self, code: i64) -> Option<OpCode> {
+        match self.custom.get(&code) {
+            Some((opcode, _)) => Some(OpCode::Custom(*opcode)),
+            None => OpCode::from::<i64>(code).ok(),
+        }
+    }
+
+    /// The opcode with the given mnemonic, looked up in the custom opcodes first.
+    pub fn from_mnemonic(&self, mnemonic: &str) -> Option<OpCode> {
+        self.custom
+            .values()
+            .find(|(o, _)| o.mnemonic == mnemonic)
+            .map(|(o, _)| OpCode::Custom(*o))
+            .or_else(|| OpCode::from_mnemonic(mnemonic))
+    }
+
+    pub(super) fn decode(&self, word: &V) -> Result<Instruction, IntcodeErrorKind<V>> {
+        Instruction::decode(word, |code| {
+            self.opcode(code).ok_or(IntcodeErrorKind::UnknownOpcode)
+        })
+    }
+}
+
+impl<V: Value> Default for InstructionSet<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a custom instruction does once executed.
+enum Flow {
+    Next,
+    Jump(usize),
+    Wait,
+    Halt,
+    EndOfInput,
+    ClosedOutput,
+}
+
+/// The access given to a custom instruction to the program executing it.
+///
+/// Unless it jumps, halts or waits, the program moves to the next instruction once it is
+/// executed.
+pub struct InstructionContext<'a, V> {
+    state: &'a mut dyn ExecutionState<V>,
+    instruction: &'a Instruction,
+    input: &'a Pipe<V>,
+    output: Option<&'a Pipe<V>>,
+    flow: Flow,
+}
+
+impl<'a, V: Value> InstructionContext<'a, V> {
+    pub fn instruction(&self) -> &Instruction {
+        self.instruction
+    }
+
+    /// The address of the instruction.
+    pub fn address(&self) -> usize {
+        self.state.position()
+    }
+
+    /// The value of a parameter, resolved according to its mode.
+    pub fn read_parameter(&self, index: usize) -> Result<V, IntcodeErrorKind<V>> {
+        self.state
+            .parameter(index, self.instruction.get_parameter_mode(index))
+    }
+
+    /// Writes to the address given by a parameter, resolved according to its mode.
+    pub fn write_parameter(&mut self, index: usize, value: V) -> Result<(), IntcodeErrorKind<V>> {
+        let mode = *self.instruction.get_parameter_mode(index);
+        self.state.store_parameter(index, &mode, value)
+    }
+
+    pub fn read(&self, address: usize) -> V {
+        self.state.load(address)
+    }
+
+    pub fn write(&mut self, address: usize, value: V) {
+        self.state.store(address, value)
+    }
+
+    pub fn relative_base(&self) -> V {
+        self.state.relative_base()
+    }
+
+    pub fn set_relative_base(&mut self, relative_base: V) {
+        self.state.set_relative_base(relative_base)
+    }
+
+    /// Reads the next input. When there is none, the program waits and executes the instruction
+    /// again once it is run with more input, so the instruction should not have any effect yet.
+    /// If the input is closed, the program faults with an `EndOfInput` error instead.
+    pub fn read_input(&mut self) -> Option<V> {
+        let value = self.input.read();
+        if value.is_none() {
+            self.flow = match self.input.is_finished() {
+                true => Flow::EndOfInput,
+                false => Flow::Wait,
+            };
+        }
+        value
+    }
+
+    /// Writes a value to the output. When a bounded output is full, the program waits and
+    /// executes the instruction again once it is run, like it does for a missing input. If the
+    /// output is closed, the program faults with a `ClosedOutput` error.
+    pub fn write_output(&mut self, value: V) {
+        if let Some(output) = self.output {
+            match output.try_write(value) {
+                Ok(()) => (),
+                Err(WriteError::Full(_)) => self.flow = Flow::Wait,
+                Err(WriteError::Closed(_)) => self.flow = Flow::ClosedOutput,
+            }
+        }
+    }
+
+    /// Continues the execution at the given address.
+    pub fn jump(&mut self, address: usize) {
+        self.flow = Flow::Jump(address);
+    }
+
+    /// Ends the program, which stays on the instruction like it does on `hlt`.
+    pub fn halt(&mut self) {
+        self.flow = Flow::Halt;
+    }
+}
+
+/// The part of a program state a custom instruction can use, regardless of its memory backend.
+trait ExecutionState<V> {
+    fn position(&self) -> usize;
+    fn load(&self, address: usize) -> V;
+    fn store(&mut self, address: usize, value: V);
+    fn parameter(&self, index: usize, mode: &ParameterMode) -> Result<V, IntcodeErrorKind<V>>;
+    fn store_parameter(
+        &mut self,
+        index: usize,
+        mode: &ParameterMode,
+        value: V,
+    ) -> Result<(), IntcodeErrorKind<V>>;
+    fn relative_base(&self) -> V;
+    fn set_relative_base(&mut self, relative_base: V);
+}
+
+impl<V: Value, M: Memory<V>> ExecutionState<V> for ProgramState<V, M> {
+    fn position(&self) -> usize {
+        self.current_position
+    }
+
+    fn load(&self, address: usize) -> V {
+        self.intcode.read(address)
+    }
+
+    fn store(&mut self, address: usize, value: V) {
+        self.intcode.write(address, value)
+    }
+
+    fn parameter(&self, index: usize, mode: &ParameterMode) -> Result<V, IntcodeErrorKind<V>> {
+        self.read_parameter(index, mode)
+    }
+
+    fn store_parameter(
+        &mut self,
+        index: usize,
+        mode: &ParameterMode,
+        value: V,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        self.write_parameter(index, mode, value)
+    }
+
+    fn relative_base(&self) -> V {
+        self.relative_base.clone()
+    }
+
+    fn set_relative_base(&mut self, relative_base: V) {
+        self.relative_base = relative_base;
+    }
+}
+
+impl<V: Value, M: Memory<V>> ProgramState<V, M> {
+    pub(super) fn execute_custom(
+        &mut self,
+        instruction: &Instruction,
+        opcode: &CustomOpCode,
+        instructions: &InstructionSet<V>,
+        input: &Pipe<V>,
+        output: Option<&Pipe<V>>,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        let (_, executor) = instructions
+            .custom
+            .get(&opcode.code)
+            .ok_or(IntcodeErrorKind::UnknownOpcode)?;
+        let mut context = InstructionContext {
+            state: self,
+            instruction,
+            input,
+            output,
+            flow: Flow::Next,
+        };
+        executor.execute(&mut context)?;
+        match context.flow {
+            Flow::Next => self.current_position += 1 + opcode.arity,
+            Flow::Jump(address) => self.current_position = address,
+            Flow::Wait => self.status = ProgramStatus::Waiting,
+            Flow::Halt => self.status = ProgramStatus::Over,
+            Flow::EndOfInput => return Err(IntcodeErrorKind::EndOfInput),
+            Flow::ClosedOutput => return Err(IntcodeErrorKind::ClosedOutput),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod instruction_set_should {
+    use super::*;
+    use crate::intcode::disassembler::disassemble_with;
+    use crate::intcode::trace::TraceWriter;
+    use crate::intcode::{Intcode, Program};
+    use std::cell::RefCell;
+
+    const PRINT: CustomOpCode = CustomOpCode {
+        code: 50,
+        mnemonic: "dbg",
+        arity: 1,
+        written_parameter: None,
+    };
+
+    const EXIT: CustomOpCode = CustomOpCode {
+        code: 51,
+        mnemonic: "exit",
+        arity: 1,
+        written_parameter: None,
+    };
+
+    fn exit(context: &mut InstructionContext<i64>) -> Result<(), IntcodeErrorKind> {
+        let code = context.read_parameter(0)?;
+        context.write_output(code);
+        context.halt();
+        Ok(())
+    }
+
+    #[test]
+    fn execute_a_registered_opcode() {
+        let mut program = Program::new(Intcode::from(vec![1150, 7, 50, 7, 151, 3, 99, 42]));
+        let printed = Rc::new(RefCell::new(Vec::new()));
+        let log = printed.clone();
+        program.register_opcode(PRINT, move |context: &mut InstructionContext<i64>| {
+            log.borrow_mut().push(context.read_parameter(0)?);
+            Ok(())
+        });
+        program.register_opcode(EXIT, exit);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        assert!(program.run().unwrap());
+
+        assert_eq!(*printed.borrow(), vec![7, 42]);
+        assert_eq!(output.read(), Some(3));
+        assert_eq!(program.state.current_position, 4);
+    }
+
+    #[test]
+    fn override_a_standard_opcode() {
+        let mut program = Program::new(Intcode::from(vec![1101, 6, 7, 0, 99]));
+        let multiply = CustomOpCode {
+            code: 1,
+            mnemonic: "mul",
+            arity: 3,
+            written_parameter: Some(2),
+        };
+        program.register_opcode(multiply, |context: &mut InstructionContext<i64>| {
+            let result = context.read_parameter(0)? * context.read_parameter(1)?;
+            context.write_parameter(2, result)
+        });
+
+        program.run().unwrap();
+
+        assert_eq!(program.intcode().read(0), 42);
+    }
+
+    #[test]
+    fn wait_for_input_read_by_a_custom_opcode() {
+        let mut program = Program::new(Intcode::from(vec![52, 99]));
+        let read = CustomOpCode {
+            code: 52,
+            mnemonic: "rd",
+            arity: 0,
+            written_parameter: None,
+        };
+        program.register_opcode(read, |context: &mut InstructionContext<i64>| {
+            if let Some(value) = context.read_input() {
+                context.write(0, value);
+            }
+            Ok(())
+        });
+
+        assert!(!program.run().unwrap());
+        assert_eq!(program.state.status, ProgramStatus::Waiting);
+        program.write(5);
+        assert!(program.run().unwrap());
+
+        assert_eq!(program.intcode().read(0), 5);
+    }
+
+    #[test]
+    fn disassemble_the_registered_opcodes() {
+        let mut instructions = InstructionSet::new();
+        instructions.register(EXIT, exit);
+
+        let listing = disassemble_with(&Intcode::from(vec![151, -1, 99]), &instructions);
+
+        let statements: Vec<String> = listing
+            .lines
+            .iter()
+            .map(|l| l.statement.to_string())
+            .collect();
+        assert_eq!(statements, vec!["exit #-1", "hlt"]);
+    }
+
+    #[test]
+    fn trace_a_custom_opcode_with_its_arity() {
+        let mut program = Program::new(Intcode::from(vec![53, 5, 6, 7, 99, 6, 7, 0]));
+        let add = CustomOpCode {
+            code: 53,
+            mnemonic: "sum",
+            arity: 3,
+            written_parameter: Some(2),
+        };
+        program.register_opcode(add, |context: &mut InstructionContext<i64>| {
+            let result = context.read_parameter(0)? + context.read_parameter(1)?;
+            context.write_parameter(2, result)
+        });
+        let trace = Rc::new(RefCell::new(TraceWriter::new(Vec::new())));
+        program.add_observer(Box::new(trace.clone()));
+
+        program.run().unwrap();
+
+        assert_eq!(
+            String::from_utf8(trace.borrow().get_ref().clone()).unwrap(),
+            "0: sum [5], [6], [7] ; read 6,7 ; write [7]=13\n\
+             4: hlt\n"
+        );
+    }
+
+    #[test]
+    fn find_a_custom_opcode_by_its_mnemonic_before_a_standard_one() {
+        let mut instructions: InstructionSet = InstructionSet::new();
+        let halt = CustomOpCode {
+            code: 98,
+            mnemonic: "hlt",
+            ..EXIT
+        };
+        instructions.register(halt, exit);
+
+        assert_eq!(
+            instructions.from_mnemonic("hlt"),
+            Some(OpCode::Custom(halt))
+        );
+        assert_eq!(instructions.from_mnemonic("add"), Some(OpCode::Add));
+        assert_eq!(instructions.opcode(99), Some(OpCode::Halt));
+    }
+
+    #[test]
+    #[should_panic(expected = "opcode 100 does not fit in two digits")]
+    fn reject_an_opcode_of_more_than_two_digits() {
+        let mut instructions: InstructionSet = InstructionSet::new();
+
+        instructions.register(CustomOpCode { code: 100, ..EXIT }, exit);
+    }
+}
@@ -0,0 +1,342 @@
+use super::{Memory, OpCode, Pipe, Program, ProgramStatus, Value};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+/// Computes the inputs of a program as it asks for them, for instance to play a game or drive a
+/// robot.
+pub trait InputSource<V> {
+    /// The next input of the program, given the values it wrote since its previous input, or
+    /// `None` to leave the program waiting.
+    fn next_input(&mut self, outputs: &[V]) -> Option<V>;
+}
+
+impl<V, F: FnMut(&[V]) -> Option<V>> InputSource<V> for F {
+    fn next_input(&mut self, outputs: &[V]) -> Option<V> {
+        self(outputs)
+    }
+}
+
+/// Receives the outputs of a program as it writes them.
+pub trait OutputSink<V> {
+    fn write(&mut self, value: V);
+}
+
+impl<V, F: FnMut(V)> OutputSink<V> for F {
+    fn write(&mut self, value: V) {
+        self(value)
+    }
+}
+
+impl<V: Value> OutputSink<V> for Pipe<V> {
+    fn write(&mut self, value: V) {
+        Pipe::write(self, value)
+    }
+}
+
+/// A source of inputs known in advance.
+pub struct FixedInput<V = i64> {
+    values: VecDeque<V>,
+}
+
+impl<V> FixedInput<V> {
+    pub fn new<I: IntoIterator<Item = V>>(values: I) -> Self {
+        FixedInput {
+            values: values.into_iter().collect(),
+        }
+    }
+}
+
+impl<V> InputSource<V> for FixedInput<V> {
+    fn next_input(&mut self, _: &[V]) -> Option<V> {
+        self.values.pop_front()
+    }
+}
+
+/// A source of inputs read from lines of values separated by commas or spaces, such as the
+/// standard input. It ends with the reader, or at the first line that cannot be read or holds an
+/// invalid value.
+pub struct ReaderInput<R, V = i64> {
+    reader: R,
+    values: VecDeque<V>,
+}
+
+impl<R: BufRead, V: Value> ReaderInput<R, V> {
+    pub fn new(reader: R) -> Self {
+        ReaderInput {
+            reader,
+            values: VecDeque::new(),
+        }
+    }
+
+    fn read_line(&mut self) -> Option<()> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        for value in line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|v| !v.is_empty())
+        {
+            self.values.push_back(value.parse().ok()?);
+        }
+        Some(())
+    }
+}
+
+impl<R: BufRead, V: Value> InputSource<V> for ReaderInput<R, V> {
+    fn next_input(&mut self, _: &[V]) -> Option<V> {
+        while self.values.is_empty() {
+            self.read_line()?;
+        }
+        self.values.pop_front()
+    }
+}
+
+/// A sink collecting the outputs, shared by its clones to read them once given to a program.
+#[derive(Clone)]
+pub struct VecOutput<V = i64> {
+    values: Rc<RefCell<Vec<V>>>,
+}
+
+impl<V: Clone> VecOutput<V> {
+    pub fn new() -> Self {
+        VecOutput {
+            values: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// The outputs collected so far.
+    pub fn values(&self) -> Vec<V> {
+        self.values.borrow().clone()
+    }
+
+    /// Takes the outputs collected so far, leaving the sink empty.
+    pub fn take(&self) -> Vec<V> {
+        self.values.borrow_mut().split_off(0)
+    }
+}
+
+impl<V: Clone> Default for VecOutput<V> {
+    fn default() -> Self {
+        VecOutput::new()
+    }
+}
+
+impl<V> OutputSink<V> for VecOutput<V> {
+    fn write(&mut self, value: V) {
+        self.values.borrow_mut().push(value);
+    }
+}
+
+/// A sink writing the outputs one per line, such as to the standard output. The outputs that
+/// cannot be written are lost.
+pub struct WriterOutput<W> {
+    writer: W,
+}
+
+impl<W: Write> WriterOutput<W> {
+    pub fn new(writer: W) -> Self {
+        WriterOutput { writer }
+    }
+}
+
+impl<V: Value, W: Write> OutputSink<V> for WriterOutput<W> {
+    fn write(&mut self, value: V) {
+        let _ = writeln!(self.writer, "{}", value).and_then(|_| self.writer.flush());
+    }
+}
+
+impl<V: Value, M: Memory<V>> Program<V, M> {
+    /// Sets where `run` takes the inputs of the program from each time it waits for one, once
+    /// the values written to its input are read. Without a sink, the outputs given to the source
+    /// are consumed from the output of the program.
+    pub fn set_input_source(&mut self, source: Option<Box<dyn InputSource<V>>>) {
+        self.source = source;
+        self.recent_outputs.clear();
+    }
+
+    /// Sets where `run` sends the outputs of the program, each time it stops or waits, instead
+    /// of leaving them in its output.
+    pub fn set_output_sink(&mut self, sink: Option<Box<dyn OutputSink<V>>>) {
+        if sink.is_some() && self.output.is_none() {
+            self.output = Some(Pipe::new());
+        }
+        self.sink = sink;
+    }
+
+    /// Hands the outputs of a stopped program to its sink, and a new input from its source to a
+    /// program waiting for one. Returns whether the program can run again.
+    pub(super) fn exchange(&mut self) -> bool {
+        if self.source.is_none() && self.sink.is_none() {
+            return false;
+        }
+        let mut received = false;
+        if let Some(output) = &self.output {
+            while let Some(value) = output.read() {
+                received = true;
+                if let Some(sink) = &mut self.sink {
+                    sink.write(value.clone());
+                }
+                if self.source.is_some() {
+                    self.recent_outputs.push(value);
+                }
+            }
+        }
+        if self.state.status != ProgramStatus::Waiting {
+            return false;
+        }
+        if self.is_waiting_for_input(received) {
+            let source = match &mut self.source {
+                Some(source) => source,
+                None => return false,
+            };
+            match source.next_input(&self.recent_outputs) {
+                Some(value) => self.input.write(value),
+                None => return false,
+            }
+            self.recent_outputs.clear();
+        } else if !received {
+            return false;
+        }
+        self.state.status = ProgramStatus::Running;
+        true
+    }
+
+    /// Whether a waiting program needs an input, rather than room in its output. A custom
+    /// instruction may wait for either, so it is taken as needing an input unless outputs were
+    /// just received from it.
+    fn is_waiting_for_input(&self, received: bool) -> bool {
+        if self.input.peek().is_some() {
+            return false;
+        }
+        let position = self.state.current_position;
+        match self.instructions.decode(&self.state.intcode.read(position)) {
+            Ok(instruction) => match instruction.opcode {
+                OpCode::Input => true,
+                OpCode::Custom(_) => !received,
+                _ => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod io_should {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+    use crate::intcode::{squares, Program};
+    use std::io::Cursor;
+
+    #[test]
+    fn compute_each_input_from_the_previous_outputs() {
+        let mut program = Program::new(squares());
+        let output = VecOutput::new();
+        program.set_output_sink(Some(Box::new(output.clone())));
+        program.set_input_source(Some(Box::new(|outputs: &[i64]| match outputs.last() {
+            None => Some(2),
+            Some(&square) if square < 100 => Some(square),
+            Some(_) => Some(0),
+        })));
+
+        let over = program.run().unwrap();
+
+        assert!(over);
+        assert_eq!(output.values(), vec![4, 16, 256]);
+        assert_eq!(program.output().unwrap().contents(), vec![]);
+    }
+
+    #[test]
+    fn wait_once_the_source_has_no_more_inputs() {
+        let mut program = Program::new(squares());
+        program.set_input_source(Some(Box::new(FixedInput::new(vec![2, 3]))));
+        let outputs = Rc::new(RefCell::new(Vec::new()));
+        let sink = outputs.clone();
+        program.set_output_sink(Some(Box::new(move |value| sink.borrow_mut().push(value))));
+
+        let over = program.run().unwrap();
+
+        assert!(!over);
+        assert_eq!(program.status(), ProgramStatus::Waiting);
+        assert_eq!(*outputs.borrow(), vec![4, 9]);
+    }
+
+    #[test]
+    fn read_the_inputs_from_lines_of_values() {
+        let mut program = Program::new(squares());
+        let lines = Cursor::new("2, 3\n\n4\n0\n");
+        program.set_input_source(Some(Box::new(ReaderInput::new(lines))));
+        let output = VecOutput::new();
+        program.set_output_sink(Some(Box::new(output.clone())));
+
+        program.run().unwrap();
+
+        assert_eq!(output.values(), vec![4, 9, 16]);
+        assert_eq!(program.status(), ProgramStatus::Over);
+    }
+
+    #[test]
+    fn write_the_outputs_to_a_writer_or_a_pipe() {
+        let mut program = Program::new(squares());
+        let log = Pipe::new();
+        program.set_output_sink(Some(Box::new(log.clone())));
+        program.write(2);
+        program.write(3);
+        program.run().unwrap();
+        let mut text = Vec::new();
+        let mut writer = WriterOutput::new(&mut text);
+
+        while let Some(value) = log.read() {
+            OutputSink::write(&mut writer, value);
+        }
+
+        assert_eq!(String::from_utf8(text).unwrap(), "4\n9\n");
+    }
+
+    #[test]
+    fn ask_for_an_input_only_once_the_program_reads_one() {
+        // Outputs each input twice.
+        let mut program: Program = Program::new(
+            assemble(
+                "loop:  in [x]
+                        out [x]
+                        out [x]
+                        jz #0, #loop
+                 x:     .data 0",
+            )
+            .unwrap(),
+        );
+        program.set_output(&Pipe::bounded(1));
+        let given = Rc::new(RefCell::new(Vec::new()));
+        let log = given.clone();
+        let mut inputs = vec![5, 7].into_iter();
+        program.set_input_source(Some(Box::new(move |outputs: &[i64]| {
+            log.borrow_mut().push(outputs.to_vec());
+            inputs.next()
+        })));
+
+        program.run().unwrap();
+
+        assert_eq!(*given.borrow(), vec![vec![], vec![5, 5], vec![7, 7]]);
+        assert_eq!(program.status(), ProgramStatus::Waiting);
+    }
+
+    #[test]
+    fn give_room_to_a_program_waiting_on_a_full_output() {
+        let mut program = Program::new(squares());
+        program.set_output(&Pipe::bounded(1));
+        let output = VecOutput::new();
+        program.set_output_sink(Some(Box::new(output.clone())));
+        for value in [2, 3, 4, 0] {
+            program.write(value);
+        }
+
+        let over = program.run().unwrap();
+
+        assert!(over);
+        assert_eq!(output.take(), vec![4, 9, 16]);
+        assert_eq!(output.values(), vec![]);
+    }
+}
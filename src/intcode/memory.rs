@@ -0,0 +1,302 @@
+use super::Value;
+use im_rc::{OrdMap, Vector};
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+
+/// How far past the end of the loaded program a write grows it. Writes further away are kept
+/// apart, so that a single write to a very high address does not allocate the whole range.
+const MAX_DENSE_GROWTH: usize = 4096;
+
+/// The storage behind the memory of an intcode program.
+///
+/// Memory behaves as an infinite zero-initialised storage. Every backend keeps the same notion of
+/// length: the loaded program, grown by the writes just past its end.
+pub trait Memory<V>: Clone + Debug {
+    /// Creates a memory holding the loaded program.
+    fn load(program: Vec<V>) -> Self;
+
+    fn read(&self, address: usize) -> V;
+
+    fn write(&mut self, address: usize, value: V);
+
+    /// The length of the loaded program, including the memory it grew into.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The cells kept by the memory, with their address. Any other cell holds zero.
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, V)> + '_>;
+}
+
+fn grows_to(len: usize, address: usize) -> bool {
+    address >= len && address - len < MAX_DENSE_GROWTH
+}
+
+/// A memory kept in a vector updated in place, for raw speed. Writes to very high addresses are
+/// kept in a sparse map.
+#[derive(Clone, Debug)]
+pub struct FlatMemory<V = i64> {
+    code: Vec<V>,
+    sparse: BTreeMap<usize, V>,
+}
+
+impl<V: Value> FlatMemory<V> {
+    fn grow(&mut self, len: usize) {
+        let mut sparse = self.sparse.split_off(&len);
+        while self.code.len() < len {
+            let position = self.code.len();
+            self.code
+                .push(self.sparse.remove(&position).unwrap_or_else(|| V::from(0)));
+        }
+        if let Some(value) = sparse.remove(&len) {
+            self.code.push(value);
+        }
+        self.sparse = sparse;
+    }
+}
+
+impl<V: Value> Memory<V> for FlatMemory<V> {
+    fn load(program: Vec<V>) -> Self {
+        FlatMemory {
+            code: program,
+            sparse: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    fn read(&self, address: usize) -> V {
+        match self.code.get(address) {
+            Some(value) => value.clone(),
+            None => self
+                .sparse
+                .get(&address)
+                .cloned()
+                .unwrap_or_else(|| V::from(0)),
+        }
+    }
+
+    #[inline]
+    fn write(&mut self, address: usize, value: V) {
+        if address < self.code.len() {
+            self.code[address] = value;
+        } else if grows_to(self.code.len(), address) {
+            self.grow(address + 1);
+            self.code[address] = value;
+        } else {
+            self.sparse.insert(address, value);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, V)> + '_> {
+        Box::new(
+            self.code
+                .iter()
+                .cloned()
+                .enumerate()
+                .chain(self.sparse.iter().map(|(a, v)| (*a, v.clone()))),
+        )
+    }
+}
+
+/// A memory kept in persistent structures, so that clones share most of their cells. It suits
+/// programs that are cloned often, such as snapshots of a running program. Its cells are shared
+/// without synchronisation, so it cannot be sent to another thread.
+#[derive(Clone)]
+pub struct PersistentMemory<V = i64> {
+    code: Vector<V>,
+    sparse: OrdMap<usize, V>,
+}
+
+impl<V: Value> PersistentMemory<V> {
+    fn grow(&mut self, len: usize) {
+        let (moved, next, sparse) = self.sparse.split_lookup(&len);
+        while self.code.len() < len {
+            let position = self.code.len();
+            self.code
+                .push_back(moved.get(&position).cloned().unwrap_or_else(|| V::from(0)));
+        }
+        if let Some(value) = next {
+            self.code.push_back(value);
+        }
+        self.sparse = sparse;
+    }
+}
+
+impl<V: Value> Debug for PersistentMemory<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("PersistentMemory")
+            .field("code", &self.code)
+            .field("sparse", &self.sparse)
+            .finish()
+    }
+}
+
+impl<V: Value> Memory<V> for PersistentMemory<V> {
+    fn load(program: Vec<V>) -> Self {
+        PersistentMemory {
+            code: Vector::from(program),
+            sparse: OrdMap::new(),
+        }
+    }
+
+    fn read(&self, address: usize) -> V {
+        match self.code.get(address) {
+            Some(value) => value.clone(),
+            None => self
+                .sparse
+                .get(&address)
+                .cloned()
+                .unwrap_or_else(|| V::from(0)),
+        }
+    }
+
+    fn write(&mut self, address: usize, value: V) {
+        if address < self.code.len() {
+            self.code.set(address, value);
+        } else if grows_to(self.code.len(), address) {
+            self.grow(address + 1);
+            self.code.set(address, value);
+        } else {
+            self.sparse.insert(address, value);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, V)> + '_> {
+        Box::new(
+            self.code
+                .iter()
+                .cloned()
+                .enumerate()
+                .chain(self.sparse.iter().map(|(a, v)| (*a, v.clone()))),
+        )
+    }
+}
+
+/// A memory keeping every cell in a map, for programs spreading their data over a huge address
+/// space.
+#[derive(Clone, Debug)]
+pub struct SparseMemory<V = i64> {
+    cells: BTreeMap<usize, V>,
+    len: usize,
+}
+
+impl<V: Value> Memory<V> for SparseMemory<V> {
+    fn load(program: Vec<V>) -> Self {
+        SparseMemory {
+            len: program.len(),
+            cells: program.into_iter().enumerate().collect(),
+        }
+    }
+
+    fn read(&self, address: usize) -> V {
+        self.cells
+            .get(&address)
+            .cloned()
+            .unwrap_or_else(|| V::from(0))
+    }
+
+    fn write(&mut self, address: usize, value: V) {
+        if grows_to(self.len, address) {
+            self.len = address + 1;
+        }
+        self.cells.insert(address, value);
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, V)> + '_> {
+        Box::new(self.cells.iter().map(|(a, v)| (*a, v.clone())))
+    }
+}
+
+#[cfg(test)]
+mod memory_should {
+    use super::*;
+
+    fn write_past_the_end<M: Memory<i64>>() -> M {
+        let mut memory = M::load(vec![1, 2, 3]);
+        memory.write(10_000, 7);
+        memory.write(7_000, 8);
+        memory.write(4_000, 9);
+        memory.write(8_000, 10);
+        memory
+    }
+
+    fn grow_only_on_writes_near_its_end<M: Memory<i64>>() {
+        let memory: M = write_past_the_end();
+
+        assert_eq!(memory.len(), 8_001);
+        assert_eq!(memory.read(10_000), 7);
+        assert_eq!(memory.read(7_000), 8);
+        assert_eq!(memory.read(4_000), 9);
+        assert_eq!(memory.read(8_000), 10);
+        assert_eq!(memory.read(5_000), 0);
+        assert_eq!(memory.read(1), 2);
+    }
+
+    #[test]
+    fn grow_the_same_way_with_every_backend() {
+        grow_only_on_writes_near_its_end::<FlatMemory>();
+        grow_only_on_writes_near_its_end::<PersistentMemory>();
+        grow_only_on_writes_near_its_end::<SparseMemory>();
+    }
+
+    #[test]
+    fn move_sparse_values_in_the_flat_memory_when_it_grows() {
+        let memory: FlatMemory = write_past_the_end();
+
+        assert_eq!(memory.code.len(), 8_001);
+        assert_eq!(memory.sparse.keys().collect::<Vec<_>>(), vec![&10_000]);
+    }
+
+    #[test]
+    fn move_sparse_values_in_the_persistent_memory_when_it_grows() {
+        let memory: PersistentMemory = write_past_the_end();
+
+        assert_eq!(memory.code.len(), 8_001);
+        assert_eq!(memory.sparse.keys().collect::<Vec<_>>(), vec![&10_000]);
+    }
+
+    fn keep_a_sparse_value_just_past_the_end_it_grows_to<M: Memory<i64>>() {
+        let mut memory = M::load(vec![1, 2, 3]);
+        memory.write(4_099, 42);
+
+        memory.write(4_098, 7);
+
+        assert_eq!(memory.read(4_099), 42);
+        assert_eq!(memory.read(4_098), 7);
+        assert_eq!(memory.cells().filter(|(a, _)| *a == 4_099).count(), 1);
+    }
+
+    #[test]
+    fn keep_a_sparse_value_just_past_the_end_with_every_backend() {
+        keep_a_sparse_value_just_past_the_end_it_grows_to::<FlatMemory>();
+        keep_a_sparse_value_just_past_the_end_it_grows_to::<PersistentMemory>();
+        keep_a_sparse_value_just_past_the_end_it_grows_to::<SparseMemory>();
+    }
+
+    #[test]
+    fn keep_only_the_cells_written_in_a_sparse_memory() {
+        let mut memory: SparseMemory = Memory::load(vec![1, 2]);
+
+        memory.write(1 << 40, 3);
+
+        assert_eq!(
+            memory.cells().collect::<Vec<_>>(),
+            vec![(0, 1), (1, 2), (1 << 40, 3)]
+        );
+        assert_eq!(memory.len(), 2);
+    }
+}
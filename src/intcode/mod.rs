@@ -0,0 +1,1516 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::iter::FromIterator;
+use std::ops::{Add, Mul};
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub mod ascii;
+pub mod assembler;
+pub mod control_flow;
+mod cycle;
+pub mod debugger;
+pub mod disassembler;
+mod history;
+mod instruction_set;
+pub mod io;
+mod memory;
+mod outputs;
+mod pipe;
+pub mod profiler;
+mod save;
+pub mod self_modification;
+mod snapshot;
+pub mod trace;
+
+pub use history::HistoryLimits;
+pub use instruction_set::{CustomOpCode, InstructionContext, InstructionExecutor, InstructionSet};
+pub use memory::{FlatMemory, Memory, PersistentMemory, SparseMemory};
+pub use outputs::Outputs;
+pub use pipe::{Pipe, PipeStats, SyncPipe, WriteError};
+pub use save::{is_saved_program, LoadError, LoadErrorKind};
+pub use snapshot::Snapshot;
+
+use cycle::CycleDetector;
+use history::History;
+use io::{InputSource, OutputSink};
+use self_modification::{SelfModification, SelfModificationDetector};
+use trace::{ExecutionObserver, InstructionEvent};
+
+/// The type of the cells of an intcode program.
+///
+/// It is implemented for `i32`, `i64` and `i128`, and for `num_bigint::BigInt` when the
+/// `bigint` feature is enabled. Programs use `i64` unless told otherwise.
+pub trait Value:
+    Clone + Ord + Debug + Display + FromStr + From<i32> + Add<Output = Self> + Mul<Output = Self>
+{
+    /// Converts the value to a memory address, if it is one.
+    fn to_address(&self) -> Option<usize>;
+
+    /// Converts the value to an `i64`, used to decode instructions.
+    fn to_i64(&self) -> Option<i64>;
+
+    /// Converts a memory address to a value, if it fits.
+    fn from_address(address: usize) -> Option<Self>;
+}
+
+macro_rules! impl_value_for_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl Value for $t {
+                fn to_address(&self) -> Option<usize> {
+                    usize::try_from(*self).ok()
+                }
+
+                fn to_i64(&self) -> Option<i64> {
+                    i64::try_from(*self).ok()
+                }
+
+                fn from_address(address: usize) -> Option<Self> {
+                    <$t>::try_from(address).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_value_for_primitive!(i32, i64, i128);
+
+#[cfg(feature = "bigint")]
+impl Value for num_bigint::BigInt {
+    fn to_address(&self) -> Option<usize> {
+        num_traits::ToPrimitive::to_usize(self)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        num_traits::ToPrimitive::to_i64(self)
+    }
+
+    fn from_address(address: usize) -> Option<Self> {
+        Some(num_bigint::BigInt::from(address))
+    }
+}
+
+pub struct Program<V = i64, M = PersistentMemory<V>> {
+    state: ProgramState<V, M>,
+    input: Pipe<V>,
+    output: Option<Pipe<V>>,
+    error: Option<IntcodeError<V>>,
+    observers: Vec<Box<dyn ExecutionObserver<V>>>,
+    instructions: InstructionSet<V>,
+    budget: Option<usize>,
+    executed: usize,
+    cycle_detector: Option<CycleDetector<V, M>>,
+    modification_detector: Option<SelfModificationDetector>,
+    history: Option<History<V, M>>,
+    source: Option<Box<dyn InputSource<V>>>,
+    sink: Option<Box<dyn OutputSink<V>>>,
+    recent_outputs: Vec<V>,
+}
+
+impl<V: Value, M: Memory<V>> Program<V, M> {
+    /// Creates a program running in the given memory backend, as in
+    /// `Program::<i64, SparseMemory>::with_memory(intcode)`.
+    pub fn with_memory(intcode: Intcode<V>) -> Self {
+        Program::new(intcode.with_memory())
+    }
+
+    /// Creates a program running in the memory backend of the intcode, so that an intcode moved
+    /// to another backend once can be cloned into many programs.
+    pub fn new(intcode: Intcode<V, M>) -> Self {
+        Program {
+            state: ProgramState {
+                status: ProgramStatus::Running,
+                intcode,
+                current_position: 0,
+                relative_base: V::from(0),
+            },
+            input: Pipe::new(),
+            output: None,
+            error: None,
+            observers: Vec::new(),
+            instructions: InstructionSet::new(),
+            budget: None,
+            executed: 0,
+            cycle_detector: None,
+            modification_detector: None,
+            history: None,
+            source: None,
+            sink: None,
+            recent_outputs: Vec::new(),
+        }
+    }
+
+    pub fn connect<N>(&mut self, program: &Program<V, N>) {
+        self.output = Some(program.input.clone());
+    }
+
+    pub fn set_input(&mut self, input: &Pipe<V>) {
+        self.input = input.clone();
+    }
+
+    pub fn set_output(&mut self, output: &Pipe<V>) {
+        self.output = Some(output.clone());
+    }
+
+    pub fn write(&self, value: V) {
+        self.input.write(value);
+    }
+
+    pub fn input(&self) -> &Pipe<V> {
+        &self.input
+    }
+
+    pub fn output(&self) -> Option<&Pipe<V>> {
+        self.output.as_ref()
+    }
+
+    /// The memory of the program.
+    pub fn intcode(&self) -> &Intcode<V, M> {
+        &self.state.intcode
+    }
+
+    /// The next value waiting in the output, left in it. `take_output` consumes it.
+    pub fn read(&self) -> Option<V> {
+        self.output.as_ref().and_then(|p| p.peek())
+    }
+
+    /// Adds an observer notified of each instruction executed from now on.
+    pub fn add_observer(&mut self, observer: Box<dyn ExecutionObserver<V>>) {
+        self.observers.push(observer);
+    }
+
+    /// Adds a custom opcode, or overrides an existing one. Returns the custom opcode it replaces,
+    /// if any.
+    pub fn register_opcode<E: InstructionExecutor<V> + 'static>(
+        &mut self,
+        opcode: CustomOpCode,
+        executor: E,
+    ) -> Option<CustomOpCode> {
+        self.instructions.register(opcode, executor)
+    }
+
+    /// Replaces the opcodes understood by the program, for instance to share them between the
+    /// programs of a network.
+    pub fn set_instruction_set(&mut self, instructions: &InstructionSet<V>) {
+        self.instructions = instructions.clone();
+    }
+
+    pub fn instruction_set(&self) -> &InstructionSet<V> {
+        &self.instructions
+    }
+
+    pub fn status(&self) -> ProgramStatus {
+        self.state.status.clone()
+    }
+
+    /// The number of instructions executed since the program was created, including the ones
+    /// undone by going back in its history.
+    pub fn instruction_count(&self) -> usize {
+        self.executed
+    }
+
+    /// Limits the number of instructions executed by each call to `run`. Once they are executed,
+    /// the program stops with the `BudgetExhausted` status, and the next call to `run` resumes it.
+    pub fn set_step_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    /// Enables the detection of infinite loops: a program going back to the same instruction
+    /// pointer, relative base and memory without reading or writing a value faults with an
+    /// `InfiniteLoop` error. It slows the execution down, as the memory is compared regularly.
+    pub fn set_cycle_detection(&mut self, enabled: bool) {
+        self.cycle_detector = match enabled {
+            true => Some(CycleDetector::new()),
+            false => None,
+        };
+    }
+
+    /// Enables the detection of writes of the program into its own instructions, reported by
+    /// `self_modifications`. Disabling it forgets the writes detected so far.
+    pub fn set_self_modification_detection(&mut self, enabled: bool) {
+        self.modification_detector = match enabled {
+            true => Some(SelfModificationDetector::new()),
+            false => None,
+        };
+    }
+
+    /// The writes of the program into its own instructions detected so far.
+    pub fn self_modifications(&self) -> Vec<SelfModification> {
+        self.modification_detector
+            .as_ref()
+            .map(|d| d.modifications())
+            .unwrap_or_default()
+    }
+
+    /// Runs the program until it is over, waits for input, faults or exhausts its step budget.
+    /// Inputs are taken from its input source, if any, and outputs given to its output sink.
+    /// Returns whether the program is over.
+    pub fn run(&mut self) -> Result<bool, IntcodeError<V>> {
+        match self.state.status {
+            ProgramStatus::Over | ProgramStatus::Faulted => (),
+            _ => self.state.status = ProgramStatus::Running,
+        }
+        let mut steps = 0;
+        loop {
+            while self.state.status == ProgramStatus::Running {
+                if Some(steps) == self.budget {
+                    self.state.status = ProgramStatus::BudgetExhausted;
+                    break;
+                }
+                self.execute();
+                steps += 1;
+            }
+            if !self.exchange() {
+                break;
+            }
+        }
+        match &self.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(self.state.status == ProgramStatus::Over),
+        }
+    }
+
+    fn execute(&mut self) {
+        match self.execute_instruction() {
+            Ok(()) if self.state.status == ProgramStatus::Waiting => (),
+            Ok(()) => self.executed += 1,
+            Err(kind) => {
+                self.error = Some(IntcodeError {
+                    kind,
+                    instruction_pointer: self.state.current_position,
+                    instruction: self.state.intcode.read(self.state.current_position),
+                });
+                self.state.status = ProgramStatus::Faulted;
+            }
+        }
+    }
+
+    fn execute_instruction(&mut self) -> Result<(), IntcodeErrorKind<V>> {
+        let instruction = self.state.next_instruction(&self.instructions)?;
+        if self.observers.is_empty()
+            && self.cycle_detector.is_none()
+            && self.modification_detector.is_none()
+            && self.history.is_none()
+        {
+            return self.state.execute(
+                &instruction,
+                &self.instructions,
+                &self.input,
+                self.output.as_ref(),
+            );
+        }
+        if let Some(detector) = &mut self.cycle_detector {
+            if detector.is_repeated(&instruction, &self.state) {
+                return Err(IntcodeErrorKind::InfiniteLoop);
+            }
+        }
+        let mut event = match self.observers.is_empty() && self.modification_detector.is_none() {
+            true => None,
+            false => Some(InstructionEvent::start(instruction.clone(), &self.state)),
+        };
+        let step = match &mut self.history {
+            Some(history) => Some(history.start_step(
+                &instruction,
+                &self.state,
+                &self.input,
+                self.output.as_ref(),
+            )),
+            None => None,
+        };
+        self.state.execute(
+            &instruction,
+            &self.instructions,
+            &self.input,
+            self.output.as_ref(),
+        )?;
+        if self.state.status == ProgramStatus::Waiting {
+            return Ok(());
+        }
+        if let (Some(history), Some(step)) = (&mut self.history, step) {
+            history.end_step(step, &self.state, &self.input, self.output.as_ref());
+        }
+        if let Some(event) = &mut event {
+            event.finish(&self.state);
+            if let Some(detector) = &mut self.modification_detector {
+                detector.on_instruction(event);
+            }
+            for observer in self.observers.iter_mut() {
+                observer.on_instruction(event);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct ProgramState<V = i64, M = PersistentMemory<V>> {
+    pub status: ProgramStatus,
+    intcode: Intcode<V, M>,
+    current_position: usize,
+    relative_base: V,
+}
+
+impl<V: Value, M: Memory<V>> ProgramState<V, M> {
+    /// Decodes the instruction at the instruction pointer.
+    #[inline]
+    fn next_instruction(
+        &self,
+        instructions: &InstructionSet<V>,
+    ) -> Result<Instruction, IntcodeErrorKind<V>> {
+        let position = self.current_position;
+        match instructions.is_standard() {
+            true => self.intcode.instruction(position),
+            false => instructions.decode(&self.intcode.read(position)),
+        }
+    }
+
+    #[inline]
+    fn read_parameter(&self, index: usize, mode: &ParameterMode) -> Result<V, IntcodeErrorKind<V>> {
+        let value = self.intcode.read(self.current_position + 1 + index);
+        match mode {
+            ParameterMode::Position => Ok(self.intcode.read(to_address(value)?)),
+            ParameterMode::Immediate => Ok(value),
+            ParameterMode::Relative => Ok(self
+                .intcode
+                .read(to_address(self.relative_base.clone() + value)?)),
+        }
+    }
+
+    #[inline]
+    fn write_parameter(
+        &mut self,
+        index: usize,
+        mode: &ParameterMode,
+        value: V,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        let address = self.parameter_address(index, mode)?;
+        self.intcode.write(address, value);
+        Ok(())
+    }
+
+    /// The address a parameter refers to. Parameters in immediate mode do not refer to any, and
+    /// cannot be written to.
+    #[inline]
+    fn parameter_address(
+        &self,
+        index: usize,
+        mode: &ParameterMode,
+    ) -> Result<usize, IntcodeErrorKind<V>> {
+        let parameter = self.intcode.read(self.current_position + 1 + index);
+        let position = match mode {
+            ParameterMode::Position => parameter,
+            ParameterMode::Immediate => return Err(IntcodeErrorKind::WriteInImmediateMode),
+            ParameterMode::Relative => self.relative_base.clone() + parameter,
+        };
+        to_address(position)
+    }
+
+    /// Executes an instruction, updating the state in place. A standard instruction that faults
+    /// leaves the memory and the instruction pointer unchanged, but a custom one keeps what its
+    /// executor wrote before returning an error.
+    fn execute(
+        &mut self,
+        instruction: &Instruction,
+        instructions: &InstructionSet<V>,
+        input: &Pipe<V>,
+        output: Option<&Pipe<V>>,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        self.status = ProgramStatus::Running;
+        match instruction.opcode {
+            OpCode::Add => self.compute(instruction, |a, b| a + b),
+            OpCode::Multiply => self.compute(instruction, |a, b| a * b),
+            OpCode::Input => self.input(instruction, input),
+            OpCode::Output => self.output(instruction, output),
+            OpCode::JumpIfTrue => self.jump(instruction, |v| v != V::from(0)),
+            OpCode::JumpIfFalse => self.jump(instruction, |v| v == V::from(0)),
+            OpCode::LessThan => self.compute(instruction, |a, b| V::from((a < b) as i32)),
+            OpCode::Equals => self.compute(instruction, |a, b| V::from((a == b) as i32)),
+            OpCode::AdjustRelativeBase => self.adjust_relative_base(instruction),
+            OpCode::Halt => {
+                self.status = ProgramStatus::Over;
+                Ok(())
+            }
+            OpCode::Custom(opcode) => {
+                self.execute_custom(instruction, &opcode, instructions, input, output)
+            }
+        }
+    }
+
+    fn compute<F: Fn(V, V) -> V>(
+        &mut self,
+        instruction: &Instruction,
+        operation: F,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        let parameter1_value = self.read_parameter(0, instruction.get_parameter_mode(0))?;
+        let parameter2_value = self.read_parameter(1, instruction.get_parameter_mode(1))?;
+        let result = operation(parameter1_value, parameter2_value);
+        self.write_parameter(2, instruction.get_parameter_mode(2), result)?;
+        self.current_position += 4;
+        Ok(())
+    }
+
+    fn input(
+        &mut self,
+        instruction: &Instruction,
+        input: &Pipe<V>,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        match input.read() {
+            Some(value) => {
+                self.write_parameter(0, instruction.get_parameter_mode(0), value)?;
+                self.current_position += 2;
+            }
+            None if input.is_finished() => return Err(IntcodeErrorKind::EndOfInput),
+            None => self.status = ProgramStatus::Waiting,
+        }
+        Ok(())
+    }
+
+    fn output(
+        &mut self,
+        instruction: &Instruction,
+        output: Option<&Pipe<V>>,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        let value = self.read_parameter(0, instruction.get_parameter_mode(0))?;
+        if let Some(output) = output {
+            match output.try_write(value) {
+                Ok(()) => (),
+                Err(WriteError::Full(_)) => {
+                    self.status = ProgramStatus::Waiting;
+                    return Ok(());
+                }
+                Err(WriteError::Closed(_)) => return Err(IntcodeErrorKind::ClosedOutput),
+            }
+        }
+        self.current_position += 2;
+        Ok(())
+    }
+
+    fn jump<F: Fn(V) -> bool>(
+        &mut self,
+        instruction: &Instruction,
+        condition: F,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        let value = self.read_parameter(0, instruction.get_parameter_mode(0))?;
+        self.current_position = match condition(value) {
+            true => to_address(self.read_parameter(1, instruction.get_parameter_mode(1))?)?,
+            false => self.current_position + 3,
+        };
+        Ok(())
+    }
+
+    fn adjust_relative_base(
+        &mut self,
+        instruction: &Instruction,
+    ) -> Result<(), IntcodeErrorKind<V>> {
+        let value = self.read_parameter(0, instruction.get_parameter_mode(0))?;
+        self.relative_base = self.relative_base.clone() + value;
+        self.current_position += 2;
+        Ok(())
+    }
+}
+
+fn to_address<V: Value>(value: V) -> Result<usize, IntcodeErrorKind<V>> {
+    value
+        .to_address()
+        .ok_or(IntcodeErrorKind::InvalidAddress(value))
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ProgramStatus {
+    Running,
+    /// The program waits for a value to read, or for room to write a value to a bounded output.
+    Waiting,
+    /// The program executed the number of instructions allowed for a run, and can be run again.
+    BudgetExhausted,
+    Over,
+    Faulted,
+}
+
+/// An error raised by a faulty intcode program, with the position and the value of the
+/// instruction that caused it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct IntcodeError<V = i64> {
+    pub kind: IntcodeErrorKind<V>,
+    pub instruction_pointer: usize,
+    pub instruction: V,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum IntcodeErrorKind<V = i64> {
+    UnknownOpcode,
+    InvalidAddress(V),
+    InvalidParameterMode(i64),
+    WriteInImmediateMode,
+    /// The program went back to a state it was already in, without any input or output since.
+    InfiniteLoop,
+    /// The program read from a closed input holding no more values.
+    EndOfInput,
+    /// The program wrote to a closed output.
+    ClosedOutput,
+}
+
+impl<V: Value> Display for IntcodeError<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self.kind {
+            IntcodeErrorKind::UnknownOpcode => write!(f, "unknown opcode")?,
+            IntcodeErrorKind::InvalidAddress(address) => write!(f, "invalid address {}", address)?,
+            IntcodeErrorKind::InvalidParameterMode(mode) => {
+                write!(f, "invalid parameter mode {}", mode)?
+            }
+            IntcodeErrorKind::WriteInImmediateMode => write!(f, "write in immediate mode")?,
+            IntcodeErrorKind::InfiniteLoop => write!(f, "infinite loop")?,
+            IntcodeErrorKind::EndOfInput => write!(f, "read past the end of the input")?,
+            IntcodeErrorKind::ClosedOutput => write!(f, "write to a closed output")?,
+        }
+        write!(
+            f,
+            " in instruction {} at position {}",
+            self.instruction, self.instruction_pointer
+        )
+    }
+}
+
+impl<V: Value> Error for IntcodeError<V> {}
+
+/// The memory of an intcode program.
+///
+/// Memory behaves as an infinite zero-initialised storage, kept in one of the `Memory` backends.
+/// Instructions are decoded once when the program is loaded, and the decoding is shared by its
+/// clones.
+#[derive(Clone)]
+pub struct Intcode<V = i64, M = PersistentMemory<V>> {
+    memory: M,
+    /// The instructions decoded when the program was loaded, with the word they were decoded from.
+    decoded: Arc<Vec<Option<(V, Instruction)>>>,
+}
+
+impl<V: Value, M: Memory<V>> Intcode<V, M> {
+    /// The length of the loaded program, including the memory it grew into.
+    pub fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+
+    #[inline]
+    pub fn read(&self, position: usize) -> V {
+        self.memory.read(position)
+    }
+
+    #[inline]
+    pub fn write(&mut self, position: usize, value: V) {
+        self.memory.write(position, value)
+    }
+
+    pub fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Copies the program into another memory backend.
+    pub fn with_memory<N: Memory<V>>(&self) -> Intcode<V, N> {
+        let mut memory = N::load((0..self.len()).map(|p| self.read(p)).collect());
+        for (position, value) in self.memory.cells().filter(|(p, _)| *p >= self.len()) {
+            memory.write(position, value);
+        }
+        Intcode {
+            memory,
+            decoded: self.decoded.clone(),
+        }
+    }
+
+    /// Decodes the instruction at the given position, reusing its decoding from when the program
+    /// was loaded unless it was modified since.
+    #[inline]
+    fn instruction(&self, position: usize) -> Result<Instruction, IntcodeErrorKind<V>> {
+        let word = self.read(position);
+        match self.decoded.get(position) {
+            Some(Some((loaded, instruction))) if *loaded == word => Ok(instruction.clone()),
+            _ => Instruction::from(&word),
+        }
+    }
+}
+
+impl<V: Value, M: Memory<V>> PartialEq for Intcode<V, M> {
+    fn eq(&self, other: &Self) -> bool {
+        let len = self.len().max(other.len());
+        (0..len).all(|position| self.read(position) == other.read(position))
+            && self.memory.cells().all(|(p, v)| other.read(p) == v)
+            && other.memory.cells().all(|(p, v)| self.read(p) == v)
+    }
+}
+
+impl<V: Value, M: Memory<V>> Debug for Intcode<V, M> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Intcode")
+            .field("memory", &self.memory)
+            .finish()
+    }
+}
+
+impl<V: Value> FromIterator<V> for Intcode<V> {
+    fn from_iter<T: IntoIterator<Item = V>>(iter: T) -> Self {
+        Intcode::from(Vec::from_iter(iter))
+    }
+}
+
+impl<V: Value> FromStr for Intcode<V> {
+    type Err = V::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',').map(|i| i.trim().parse::<V>()).collect()
+    }
+}
+
+/// Loads a program in a `PersistentMemory`. Use `Intcode::with_memory` to move it to another
+/// backend.
+impl<V: Value> From<Vec<V>> for Intcode<V> {
+    fn from(vec: Vec<V>) -> Self {
+        let decoded = vec
+            .iter()
+            .map(|word| Instruction::from(word).ok().map(|i| (word.clone(), i)))
+            .collect();
+        Intcode {
+            memory: PersistentMemory::load(vec),
+            decoded: Arc::new(decoded),
+        }
+    }
+}
+
+/// The number of parameters whose mode is kept by a decoded instruction, which bounds the arity of
+/// custom opcodes.
+const MAX_ARITY: usize = 8;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Instruction {
+    opcode: OpCode,
+    parameters_mode: [ParameterMode; MAX_ARITY],
+    /// The number of mode digits of the instruction code, which may exceed its arity.
+    modes_count: usize,
+}
+
+impl Instruction {
+    fn from<V: Value>(instruction_code: &V) -> Result<Self, IntcodeErrorKind<V>> {
+        Instruction::decode(instruction_code, OpCode::from)
+    }
+
+    /// Decodes an instruction, looking up its opcode with the given function.
+    #[inline]
+    fn decode<V: Value, F: FnOnce(i64) -> Result<OpCode, IntcodeErrorKind<V>>>(
+        instruction_code: &V,
+        opcode: F,
+    ) -> Result<Self, IntcodeErrorKind<V>> {
+        let instruction_code = instruction_code
+            .to_i64()
+            .ok_or(IntcodeErrorKind::UnknownOpcode)?;
+        let opcode = opcode(instruction_code % 100)?;
+        let mut parameters_mode = [ParameterMode::Position; MAX_ARITY];
+        let mut modes_count = 0;
+        let mut rest = instruction_code / 100;
+        while rest > 0 {
+            let mode = match rest % 10 {
+                0 => ParameterMode::Position,
+                1 => ParameterMode::Immediate,
+                2 => ParameterMode::Relative,
+                mode => return Err(IntcodeErrorKind::InvalidParameterMode(mode)),
+            };
+            if let Some(parameter) = parameters_mode.get_mut(modes_count) {
+                *parameter = mode;
+            }
+            modes_count += 1;
+            rest /= 10;
+        }
+        Ok(Instruction {
+            opcode,
+            parameters_mode,
+            modes_count,
+        })
+    }
+
+    pub fn opcode(&self) -> &OpCode {
+        &self.opcode
+    }
+
+    pub fn get_parameter_mode(&self, parameter: usize) -> &ParameterMode {
+        self.parameters_mode
+            .get(parameter)
+            .unwrap_or(&ParameterMode::Position)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ParameterMode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl ParameterMode {
+    pub fn digit(&self) -> i64 {
+        match self {
+            Self::Position => 0,
+            Self::Immediate => 1,
+            Self::Relative => 2,
+        }
+    }
+}
+
+const OPCODES: [OpCode; 10] = [
+    OpCode::Add,
+    OpCode::Multiply,
+    OpCode::Input,
+    OpCode::Output,
+    OpCode::JumpIfTrue,
+    OpCode::JumpIfFalse,
+    OpCode::LessThan,
+    OpCode::Equals,
+    OpCode::AdjustRelativeBase,
+    OpCode::Halt,
+];
+
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub enum OpCode {
+    Add,
+    Multiply,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustRelativeBase,
+    Halt,
+    /// An opcode registered in an `InstructionSet`.
+    Custom(CustomOpCode),
+}
+
+impl OpCode {
+    fn from<V>(code: i64) -> Result<OpCode, IntcodeErrorKind<V>> {
+        match code {
+            1 => Ok(Self::Add),
+            2 => Ok(Self::Multiply),
+            3 => Ok(Self::Input),
+            4 => Ok(Self::Output),
+            5 => Ok(Self::JumpIfTrue),
+            6 => Ok(Self::JumpIfFalse),
+            7 => Ok(Self::LessThan),
+            8 => Ok(Self::Equals),
+            9 => Ok(Self::AdjustRelativeBase),
+            99 => Ok(Self::Halt),
+            _ => Err(IntcodeErrorKind::UnknownOpcode),
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<OpCode> {
+        OPCODES.iter().find(|o| o.mnemonic() == mnemonic).cloned()
+    }
+
+    pub fn code(&self) -> i64 {
+        match self {
+            Self::Add => 1,
+            Self::Multiply => 2,
+            Self::Input => 3,
+            Self::Output => 4,
+            Self::JumpIfTrue => 5,
+            Self::JumpIfFalse => 6,
+            Self::LessThan => 7,
+            Self::Equals => 8,
+            Self::AdjustRelativeBase => 9,
+            Self::Halt => 99,
+            Self::Custom(opcode) => opcode.code,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Multiply => "mul",
+            Self::Input => "in",
+            Self::Output => "out",
+            Self::JumpIfTrue => "jnz",
+            Self::JumpIfFalse => "jz",
+            Self::LessThan => "lt",
+            Self::Equals => "eq",
+            Self::AdjustRelativeBase => "arb",
+            Self::Halt => "hlt",
+            Self::Custom(opcode) => opcode.mnemonic,
+        }
+    }
+
+    /// The number of parameters of the instruction.
+    pub fn arity(&self) -> usize {
+        match self {
+            Self::Add | Self::Multiply | Self::LessThan | Self::Equals => 3,
+            Self::JumpIfTrue | Self::JumpIfFalse => 2,
+            Self::Input | Self::Output | Self::AdjustRelativeBase => 1,
+            Self::Halt => 0,
+            Self::Custom(opcode) => opcode.arity,
+        }
+    }
+
+    /// The index of the parameter the instruction writes to, if any.
+    pub fn written_parameter(&self) -> Option<usize> {
+        match self {
+            Self::Add | Self::Multiply | Self::LessThan | Self::Equals => Some(2),
+            Self::Input => Some(0),
+            Self::Custom(opcode) => opcode.written_parameter,
+            _ => None,
+        }
+    }
+}
+
+/// Outputs the square of each input, until it reads 0.
+#[cfg(test)]
+fn squares() -> Intcode {
+    Intcode::from(vec![
+        3, 15, 1006, 15, 14, 2, 15, 15, 16, 4, 16, 1105, 1, 0, 99, 0, 0,
+    ])
+}
+
+#[cfg(test)]
+mod intcode_tests {
+    use super::*;
+
+    #[test]
+    fn execute_sample_code_with_add_and_multiply() {
+        let mut program = Program::new(Intcode::from(vec![
+            1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50,
+        ]));
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(
+            program.state.intcode,
+            Intcode::from(vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50])
+        );
+    }
+
+    #[test]
+    fn sample1_should_return_1_when_input_is_8() {
+        let mut program = Program::new(Intcode::from(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8]));
+        program.write(8);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(1));
+    }
+
+    #[test]
+    fn sample1_should_return_0_when_input_is_not_8() {
+        let mut program = Program::new(Intcode::from(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8]));
+        program.write(7);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(0));
+    }
+
+    #[test]
+    fn sample2_should_return_1_when_input_is_less_than_8() {
+        let mut program = Program::new(Intcode::from(vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8]));
+        program.write(5);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(1));
+    }
+
+    #[test]
+    fn sample2_should_return_0_when_input_is_8() {
+        let mut program = Program::new(Intcode::from(vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8]));
+        program.write(8);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(0));
+    }
+
+    #[test]
+    fn sample3_should_return_1_when_input_is_8() {
+        let mut program = Program::new(Intcode::from(vec![3, 3, 1108, -1, 8, 3, 4, 3, 99]));
+        program.write(8);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(1));
+    }
+
+    #[test]
+    fn sample3_should_return_0_when_input_is_not_8() {
+        let mut program = Program::new(Intcode::from(vec![3, 3, 1108, -1, 8, 3, 4, 3, 99]));
+        program.write(9);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(0));
+    }
+
+    #[test]
+    fn sample4_should_return_1_when_input_is_less_than_8() {
+        let mut program = Program::new(Intcode::from(vec![3, 3, 1107, -1, 8, 3, 4, 3, 99]));
+        program.write(5);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(1));
+    }
+
+    #[test]
+    fn sample4_should_return_0_when_input_is_8() {
+        let mut program = Program::new(Intcode::from(vec![3, 3, 1107, -1, 8, 3, 4, 3, 99]));
+        program.write(8);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(0));
+    }
+
+    #[test]
+    fn sample5_should_return_0_when_input_is_0() {
+        let mut program = Program::new(Intcode::from(vec![
+            3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9,
+        ]));
+        program.write(0);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(0));
+    }
+
+    #[test]
+    fn sample5_should_return_1_when_input_is_not_0() {
+        let mut program = Program::new(Intcode::from(vec![
+            3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9,
+        ]));
+        program.write(2);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(1));
+    }
+
+    #[test]
+    fn sample6_should_return_0_when_input_is_0() {
+        let mut program = Program::new(Intcode::from(vec![
+            3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1,
+        ]));
+        program.write(0);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(0));
+    }
+
+    #[test]
+    fn sample6_should_return_1_when_input_is_not_0() {
+        let mut program = Program::new(Intcode::from(vec![
+            3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1,
+        ]));
+        program.write(2);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(1));
+    }
+
+    #[test]
+    fn sample7_should_return_999_when_input_is_less_than_8() {
+        let mut program = Program::new(Intcode::from(vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ]));
+        program.write(7);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(999));
+    }
+
+    #[test]
+    fn sample7_should_return_1000_when_input_is_8() {
+        let mut program = Program::new(Intcode::from(vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ]));
+        program.write(8);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(1000));
+    }
+
+    #[test]
+    fn sample7_should_return_1001_when_input_is_more_than_8() {
+        let mut program = Program::new(Intcode::from(vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ]));
+        program.write(9);
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(1001));
+    }
+
+    #[test]
+    fn it_should_wait_for_input_then_resume() {
+        let mut program = Program::new(Intcode::from(vec![3, 3, 99, 0]));
+
+        let result = program.run();
+
+        assert_eq!(result, Ok(false));
+
+        program.write(33);
+        let result = program.run();
+
+        assert_eq!(result, Ok(true));
+        assert_eq!(program.state.intcode, Intcode::from(vec![3, 3, 99, 33]));
+    }
+
+    #[test]
+    fn read_parameters_relatively_to_the_relative_base() {
+        let mut program = Program::new(Intcode::from(vec![109, 5, 204, 1, 99, 0, 42]));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(output.read(), Some(42));
+    }
+
+    #[test]
+    fn adjust_the_relative_base_with_a_relative_parameter() {
+        let mut program = Program::new(Intcode::from(vec![109, 6, 209, 1, 204, -3, 99, 3]));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.status, ProgramStatus::Over);
+        assert_eq!(program.state.relative_base, 9);
+        assert_eq!(output.read(), Some(99));
+    }
+
+    #[test]
+    fn write_input_relatively_to_the_relative_base() {
+        let mut program = Program::new(Intcode::from(vec![109, 2, 203, 5, 99, 0, 0, 0]));
+        program.write(12);
+
+        program.run().unwrap();
+
+        assert_eq!(
+            program.state.intcode,
+            Intcode::from(vec![109, 2, 203, 5, 99, 0, 0, 12])
+        );
+    }
+
+    #[test]
+    fn write_results_relatively_to_the_relative_base() {
+        let mut program = Program::new(Intcode::from(vec![109, -1, 21101, 3, 4, 9, 99, 0, 0]));
+
+        program.run().unwrap();
+
+        assert_eq!(
+            program.state.intcode,
+            Intcode::from(vec![109, -1, 21101, 3, 4, 9, 99, 0, 7])
+        );
+    }
+
+    #[test]
+    fn multiply_large_numbers_with_64_bit_values() {
+        let mut program: Program<i64> = Program::new(Intcode::from(vec![
+            1102, 34915192, 34915192, 7, 4, 7, 99, 0,
+        ]));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(output.read(), Some(1219070632396864));
+    }
+
+    #[test]
+    fn output_a_large_number_with_64_bit_values() {
+        let mut program: Program<i64> =
+            Program::new(Intcode::from(vec![104, 1125899906842624, 99]));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(output.read(), Some(1125899906842624));
+    }
+
+    #[test]
+    fn multiply_numbers_overflowing_64_bits_with_128_bit_values() {
+        let mut program: Program<i128> = Program::new(Intcode::from(vec![
+            1102,
+            4_000_000_000_000,
+            4_000_000_000_000,
+            7,
+            4,
+            7,
+            99,
+            0,
+        ]));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(output.read(), Some(16_000_000_000_000_000_000_000_000));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn multiply_numbers_with_arbitrary_precision_values() {
+        use num_bigint::BigInt;
+
+        let factor: BigInt = "100000000000000000000".parse().unwrap();
+        let mut program = Program::new(Intcode::from(vec![
+            BigInt::from(1102),
+            factor.clone(),
+            factor.clone(),
+            BigInt::from(7),
+            BigInt::from(4),
+            BigInt::from(7),
+            BigInt::from(99),
+            BigInt::from(0),
+        ]));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(output.read(), Some(factor.clone() * factor));
+    }
+
+    #[test]
+    fn output_a_copy_of_itself_using_memory_beyond_the_program() {
+        let code = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let mut program: Program<i64> = Program::new(Intcode::from(code.clone()));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        let result: Vec<i64> = std::iter::from_fn(|| output.read()).collect();
+        assert_eq!(result, code);
+    }
+
+    fn run_a_quine_in<M: Memory<i64>>() {
+        let code = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let mut program = Program::<i64, M>::with_memory(Intcode::from(code.clone()));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        let result: Vec<i64> = std::iter::from_fn(|| output.read()).collect();
+        assert_eq!(result, code);
+        assert_eq!(program.intcode().read(100), 16);
+        assert_eq!(program.intcode().len(), 102);
+    }
+
+    #[test]
+    fn run_the_same_way_with_every_memory_backend() {
+        run_a_quine_in::<FlatMemory>();
+        run_a_quine_in::<PersistentMemory>();
+        run_a_quine_in::<SparseMemory>();
+    }
+
+    #[test]
+    fn stop_when_its_step_budget_is_exhausted_and_resume() {
+        let mut program = Program::new(Intcode::from(vec![
+            1001, 12, 1, 12, 1007, 12, 10, 13, 1005, 13, 0, 99, 0, 0,
+        ]));
+        program.set_step_budget(Some(7));
+
+        assert!(!program.run().unwrap());
+        assert_eq!(program.status(), ProgramStatus::BudgetExhausted);
+        assert_eq!(program.intcode().read(12), 3);
+        assert_eq!(program.state.current_position, 4);
+
+        program.set_step_budget(None);
+        assert!(program.run().unwrap());
+        assert_eq!(program.intcode().read(12), 10);
+    }
+
+    #[test]
+    fn count_the_instructions_executed_but_not_the_waits_for_input() {
+        let mut program = Program::new(Intcode::from(vec![3, 5, 4, 5, 99, 0]));
+        program.set_output(&Pipe::new());
+
+        program.run().unwrap();
+        program.run().unwrap();
+        let waiting = program.instruction_count();
+        program.write(8);
+        program.run().unwrap();
+
+        assert_eq!(waiting, 0);
+        assert_eq!(program.instruction_count(), 3);
+    }
+
+    #[test]
+    fn read_zero_from_an_address_never_written() {
+        let mut program = Program::new(Intcode::from(vec![4, 123_456_789, 99]));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(output.read(), Some(0));
+    }
+
+    #[test]
+    fn write_and_read_at_a_very_high_address() {
+        let mut program = Program::new(Intcode::from(vec![
+            1101,
+            1,
+            2,
+            1_000_000_000,
+            4,
+            1_000_000_000,
+            99,
+        ]));
+        let output = Pipe::new();
+        program.set_output(&output);
+
+        program.run().unwrap();
+
+        assert_eq!(output.read(), Some(3));
+        assert_eq!(program.state.intcode.len(), 7);
+    }
+
+    #[test]
+    fn execute_an_instruction_modified_by_the_program() {
+        let mut program = Program::new(Intcode::from(vec![1101, 100, -1, 4, 1, 0, 0, 0, 99]));
+
+        program.run().unwrap();
+
+        assert_eq!(program.state.current_position, 4);
+        assert_eq!(program.state.intcode.read(0), 1101);
+    }
+}
+
+#[cfg(test)]
+mod program_errors_should {
+    use super::*;
+
+    #[test]
+    fn report_an_unknown_opcode() {
+        let mut program = Program::new(Intcode::from(vec![1101, 1, 2, 5, 42, 0, 99]));
+
+        let result = program.run();
+
+        assert_eq!(
+            result,
+            Err(IntcodeError {
+                kind: IntcodeErrorKind::UnknownOpcode,
+                instruction_pointer: 4,
+                instruction: 42,
+            })
+        );
+        assert_eq!(program.state.status, ProgramStatus::Faulted);
+    }
+
+    #[test]
+    fn report_a_negative_address() {
+        let mut program = Program::new(Intcode::from(vec![4, -3, 99]));
+
+        let result = program.run();
+
+        assert_eq!(
+            result,
+            Err(IntcodeError {
+                kind: IntcodeErrorKind::InvalidAddress(-3),
+                instruction_pointer: 0,
+                instruction: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn report_a_negative_address_relative_to_the_relative_base() {
+        let mut program = Program::new(Intcode::from(vec![109, 2, 204, -5, 99]));
+
+        let result = program.run();
+
+        assert_eq!(
+            result,
+            Err(IntcodeError {
+                kind: IntcodeErrorKind::InvalidAddress(-3),
+                instruction_pointer: 2,
+                instruction: 204,
+            })
+        );
+    }
+
+    #[test]
+    fn report_an_invalid_parameter_mode() {
+        let mut program = Program::new(Intcode::from(vec![1301, 1, 2, 0, 99]));
+
+        let result = program.run();
+
+        assert_eq!(
+            result,
+            Err(IntcodeError {
+                kind: IntcodeErrorKind::InvalidParameterMode(3),
+                instruction_pointer: 0,
+                instruction: 1301,
+            })
+        );
+    }
+
+    #[test]
+    fn report_a_write_in_immediate_mode() {
+        let mut program = Program::new(Intcode::from(vec![11101, 1, 2, 0, 99]));
+
+        let result = program.run();
+
+        assert_eq!(
+            result,
+            Err(IntcodeError {
+                kind: IntcodeErrorKind::WriteInImmediateMode,
+                instruction_pointer: 0,
+                instruction: 11101,
+            })
+        );
+    }
+
+    #[test]
+    fn report_the_same_error_when_run_again() {
+        let mut program = Program::new(Intcode::from(vec![42]));
+        let error = program.run().unwrap_err();
+
+        let result = program.run();
+
+        assert_eq!(result, Err(error));
+    }
+
+    #[test]
+    fn be_displayed_with_the_faulty_instruction_and_its_position() {
+        let error = IntcodeError {
+            kind: IntcodeErrorKind::InvalidAddress(-3),
+            instruction_pointer: 2,
+            instruction: 204,
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "invalid address -3 in instruction 204 at position 2"
+        );
+    }
+}
+
+#[cfg(test)]
+mod intcode_should {
+    use super::*;
+
+    #[test]
+    fn be_equal_to_an_intcode_with_the_same_values_and_trailing_zeros() {
+        let mut intcode = Intcode::from(vec![1, 2, 3]);
+
+        intcode.write(5, 0);
+
+        assert_eq!(intcode, Intcode::from(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn be_sent_to_another_thread_in_a_flat_or_sparse_memory() {
+        let intcode = Intcode::from(vec![1, 2, 3]);
+        let flat: Intcode<i64, FlatMemory> = intcode.with_memory();
+        let sparse: Intcode<i64, SparseMemory> = intcode.with_memory();
+
+        let handle = std::thread::spawn(move || (flat.read(1), sparse.read(2)));
+
+        assert_eq!(handle.join().unwrap(), (2, 3));
+    }
+
+    #[test]
+    fn grow_only_on_writes_near_its_end() {
+        let mut intcode = Intcode::from(vec![1, 2, 3]);
+
+        intcode.write(10_000, 7);
+        intcode.write(7_000, 8);
+        intcode.write(4_000, 9);
+        intcode.write(8_000, 10);
+
+        assert_eq!(intcode.len(), 8_001);
+        assert_eq!(intcode.read(10_000), 7);
+        assert_eq!(intcode.read(7_000), 8);
+        assert_eq!(intcode.read(4_000), 9);
+        assert_eq!(intcode.read(8_000), 10);
+        assert_eq!(intcode.read(5_000), 0);
+    }
+
+    #[test]
+    fn not_be_modified_by_writes_to_its_clones() {
+        let intcode = Intcode::from(vec![1, 2, 3]);
+
+        let mut modified = intcode.clone();
+        modified.write(1, 5);
+        modified.write(100_000, 6);
+
+        assert_eq!(intcode, Intcode::from(vec![1, 2, 3]));
+        assert_eq!(modified.read(1), 5);
+        assert_eq!(modified.read(100_000), 6);
+    }
+}
@@ -0,0 +1,141 @@
+use super::{IntcodeError, Memory, PersistentMemory, Pipe, Program, ProgramStatus, Value};
+
+/// An iterator running a program as its outputs are needed, created by `Program::outputs`.
+pub struct Outputs<'a, V = i64, M = PersistentMemory<V>> {
+    program: &'a mut Program<V, M>,
+    output: Pipe<V>,
+    done: bool,
+}
+
+impl<V: Value, M: Memory<V>> Program<V, M> {
+    /// Reads the next value waiting in the output, consuming it.
+    pub fn take_output(&self) -> Option<V> {
+        self.output.as_ref().and_then(|p| p.read())
+    }
+
+    /// Reads all the values waiting in the output, consuming them.
+    pub fn drain_output(&self) -> Vec<V> {
+        match &self.output {
+            Some(output) => std::iter::from_fn(|| output.read()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// An iterator over the outputs of the program: the values waiting in its output, then the
+    /// ones it writes as it runs, an instruction at a time, until it is over or waits for input.
+    /// A fault ends the iteration with its error. The step budget of the program is ignored.
+    ///
+    /// The iterator bypasses the input source and the output sink of the program: the outputs
+    /// are read from its output, and the iteration ends when the program waits for an input
+    /// without one written to it. The program gets an output if it has none.
+    pub fn outputs(&mut self) -> Outputs<'_, V, M> {
+        let output = match &self.output {
+            Some(output) => output.clone(),
+            None => {
+                let output = Pipe::new();
+                self.output = Some(output.clone());
+                output
+            }
+        };
+        Outputs {
+            program: self,
+            output,
+            done: false,
+        }
+    }
+}
+
+impl<'a, V: Value, M: Memory<V>> Iterator for Outputs<'a, V, M> {
+    type Item = Result<V, IntcodeError<V>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(value) = self.output.read() {
+                return Some(Ok(value));
+            }
+            let program = &mut *self.program;
+            match program.state.status {
+                ProgramStatus::Over => self.done = true,
+                ProgramStatus::Faulted => {
+                    self.done = true;
+                    return program.error.clone().map(Err);
+                }
+                ProgramStatus::Waiting if program.input.peek().is_none() => self.done = true,
+                _ => {
+                    program.state.status = ProgramStatus::Running;
+                    program.execute();
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod outputs_should {
+    use crate::intcode::{squares, Intcode, IntcodeErrorKind, Pipe, Program};
+
+    fn program() -> Program {
+        let mut program = Program::new(squares());
+        program.set_output(&Pipe::new());
+        program
+    }
+
+    #[test]
+    fn take_the_outputs_one_at_a_time() {
+        let mut program = program();
+        program.write(2);
+        program.write(3);
+        program.run().unwrap();
+
+        let first = program.take_output();
+        let second = program.take_output();
+
+        assert_eq!(first, Some(4));
+        assert_eq!(second, Some(9));
+        assert_eq!(program.take_output(), None);
+    }
+
+    #[test]
+    fn drain_the_pending_outputs() {
+        let mut program = program();
+        program.write(2);
+        program.write(3);
+        program.run().unwrap();
+
+        let outputs = program.drain_output();
+
+        assert_eq!(outputs, vec![4, 9]);
+        assert_eq!(program.output().unwrap().contents(), vec![]);
+    }
+
+    #[test]
+    fn run_the_program_lazily_until_it_needs_input() {
+        let mut program = program();
+        program.write(2);
+        program.write(3);
+
+        let first = program.outputs().next();
+        let position = program.state.current_position;
+        let rest: Vec<_> = program.outputs().collect();
+
+        assert_eq!(first, Some(Ok(4)));
+        assert_eq!(position, 11);
+        assert_eq!(rest, vec![Ok(9)]);
+        assert_eq!(program.status(), crate::intcode::ProgramStatus::Waiting);
+    }
+
+    #[test]
+    fn end_with_the_error_of_a_fault() {
+        let mut program = Program::new(Intcode::from(vec![104, 7, 42]));
+
+        let outputs: Vec<_> = program.outputs().collect();
+
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0], Ok(7));
+        assert_eq!(
+            outputs[1].as_ref().unwrap_err().kind,
+            IntcodeErrorKind::UnknownOpcode
+        );
+    }
+}
@@ -0,0 +1,919 @@
+use super::Value;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::rc::Rc;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// A queue of values connecting programs.
+///
+/// The pipe created by `Pipe::new` is shared between the programs of a single thread, which run
+/// in turns: reading an empty pipe returns `None` and the reading program waits to be run again.
+/// A pipe converted from a `SyncPipe` blocks its reader instead, until a value is written from
+/// another thread or the timeout of the `SyncPipe` elapses.
+///
+/// A bounded pipe holds a limited number of values: a program writing to it while it is full
+/// waits for its reader the same way. Once closed, a pipe accepts no more values, and a program
+/// reading it after its last value faults with an `EndOfInput` error instead of waiting forever.
+///
+/// Pipes also connect programs in networks other than chains: a broadcast pipe copies the values
+/// written to it to several pipes, the pipes given by `source` merge the values of several
+/// programs into a single pipe, remembering where each comes from, and a tee copies the values
+/// written to a pipe to a log.
+#[derive(Clone)]
+pub struct Pipe<V = i64> {
+    inner: PipeInner<V>,
+    /// The source the values written through this local pipe are tagged with.
+    source: Option<usize>,
+}
+
+#[derive(Clone)]
+enum PipeInner<V> {
+    Local(Rc<RefCell<Queue<V>>>),
+    Shared(SyncPipe<V>),
+    Broadcast(Rc<[Pipe<V>]>),
+    /// A target, and the log receiving a copy of the values written to it.
+    Tee(Rc<(Pipe<V>, Pipe<V>)>),
+}
+
+/// The number of values that went through a pipe.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct PipeStats {
+    pub written: usize,
+    pub read: usize,
+    /// The largest number of values the pipe held at once.
+    pub high_water_mark: usize,
+}
+
+/// A value that could not be written to a pipe, given back.
+#[derive(Clone, PartialEq, Debug)]
+pub enum WriteError<V = i64> {
+    Full(V),
+    Closed(V),
+}
+
+impl<V> Display for WriteError<V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            WriteError::Full(_) => write!(f, "the pipe is full"),
+            WriteError::Closed(_) => write!(f, "the pipe is closed"),
+        }
+    }
+}
+
+impl<V: Debug> Error for WriteError<V> {}
+
+/// The values of a pipe, with its state.
+#[derive(Clone)]
+struct Queue<V> {
+    values: VecDeque<V>,
+    /// The source of each value, once a source writes to the pipe.
+    sources: Option<VecDeque<Option<usize>>>,
+    capacity: Option<usize>,
+    closed: bool,
+    stats: PipeStats,
+}
+
+impl<V> Queue<V> {
+    fn new(capacity: Option<usize>) -> Self {
+        if let Some(capacity) = capacity {
+            assert!(capacity > 0, "The capacity of a pipe must not be 0");
+        }
+        Queue {
+            values: VecDeque::new(),
+            sources: None,
+            capacity,
+            closed: false,
+            stats: PipeStats::default(),
+        }
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<V> {
+        self.pop_tagged().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn pop_tagged(&mut self) -> Option<(Option<usize>, V)> {
+        let value = self.values.pop_front()?;
+        self.stats.read += 1;
+        let source = self.sources.as_mut().and_then(|s| s.pop_front()).flatten();
+        Some((source, value))
+    }
+
+    /// Adds a value, whatever the capacity, unless the pipe is closed.
+    #[inline]
+    fn push(&mut self, source: Option<usize>, value: V) {
+        if self.closed {
+            return;
+        }
+        self.values.push_back(value);
+        if let Some(sources) = &mut self.sources {
+            sources.push_back(source);
+        }
+        self.stats.written += 1;
+        self.stats.high_water_mark = self.stats.high_water_mark.max(self.values.len());
+    }
+
+    #[inline]
+    fn try_push(&mut self, source: Option<usize>, value: V) -> Result<(), WriteError<V>> {
+        if self.closed {
+            return Err(WriteError::Closed(value));
+        }
+        if self.is_full() {
+            return Err(WriteError::Full(value));
+        }
+        self.push(source, value);
+        Ok(())
+    }
+
+    /// Starts remembering the source of the values, the ones already there having none.
+    fn track_sources(&mut self) {
+        if self.sources.is_none() {
+            self.sources = Some(self.values.iter().map(|_| None).collect());
+        }
+    }
+
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.capacity.is_some_and(|c| self.values.len() >= c)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.closed && self.values.is_empty()
+    }
+
+    fn set_values(&mut self, values: Vec<V>) {
+        self.values = values.into();
+        if let Some(sources) = &mut self.sources {
+            *sources = self.values.iter().map(|_| None).collect();
+        }
+        self.stats.high_water_mark = self.stats.high_water_mark.max(self.values.len());
+    }
+}
+
+impl<V: Value> Pipe<V> {
+    pub fn new() -> Self {
+        Pipe::from_inner(PipeInner::Local(Rc::new(RefCell::new(Queue::new(None)))))
+    }
+
+    /// Creates a pipe holding at most `capacity` values. A program writing to it waits while it
+    /// is full, and `try_write` refuses a value it has no room for, but `write` ignores the
+    /// capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is 0.
+    pub fn bounded(capacity: usize) -> Self {
+        Pipe::from_inner(PipeInner::Local(Rc::new(RefCell::new(Queue::new(Some(
+            capacity,
+        ))))))
+    }
+
+    /// Creates a pipe writing a copy of each value written to it to each of the targets, read
+    /// independently. It holds no values itself, and keeps no statistics. A program writing to
+    /// it waits while one of the targets is full, and faults once they are all closed.
+    pub fn broadcast(targets: &[Pipe<V>]) -> Self {
+        Pipe::from_inner(PipeInner::Broadcast(targets.to_vec().into()))
+    }
+
+    /// Creates a pipe writing to the target, and copying the values written to the log. Reading
+    /// it reads the target.
+    pub fn tee(target: &Pipe<V>, log: &Pipe<V>) -> Self {
+        Pipe::from_inner(PipeInner::Tee(Rc::new((target.clone(), log.clone()))))
+    }
+
+    /// A pipe writing into this one, the values it writes tagged with the given source, read by
+    /// `read_tagged`. Several programs writing to the sources of a pipe merge their values into
+    /// it. A broadcast pipe does not hold values to tag, and gives a copy of itself.
+    pub fn source(&self, source: usize) -> Self {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow_mut().track_sources(),
+            PipeInner::Shared(pipe) => return Pipe::from(pipe.source(source)),
+            PipeInner::Broadcast(_) => return self.clone(),
+            PipeInner::Tee(tee) => return Pipe::tee(&tee.0.source(source), &tee.1),
+        }
+        Pipe {
+            inner: self.inner.clone(),
+            source: Some(source),
+        }
+    }
+
+    fn from_inner(inner: PipeInner<V>) -> Self {
+        Pipe {
+            inner,
+            source: None,
+        }
+    }
+
+    pub fn read(&self) -> Option<V> {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow_mut().pop(),
+            PipeInner::Shared(pipe) => pipe.read(),
+            PipeInner::Broadcast(_) => None,
+            PipeInner::Tee(tee) => tee.0.read(),
+        }
+    }
+
+    /// Reads the next value, with the source it was written through, if any.
+    pub fn read_tagged(&self) -> Option<(Option<usize>, V)> {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow_mut().pop_tagged(),
+            PipeInner::Shared(pipe) => pipe.read_tagged(),
+            PipeInner::Broadcast(_) => None,
+            PipeInner::Tee(tee) => tee.0.read_tagged(),
+        }
+    }
+
+    /// Writes a value. A local pipe cannot wait for its reader, which runs on the same thread:
+    /// the value is written even if the pipe is full. Values written to a closed pipe are
+    /// dropped.
+    pub fn write(&self, value: V) {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow_mut().push(self.source, value),
+            PipeInner::Shared(pipe) => pipe.write(value),
+            PipeInner::Broadcast(targets) => {
+                for target in targets.iter() {
+                    target.write(value.clone());
+                }
+            }
+            PipeInner::Tee(tee) => {
+                tee.1.write(value.clone());
+                tee.0.write(value);
+            }
+        }
+    }
+
+    /// Writes a value unless the pipe is full or closed, waiting for room within the timeout of
+    /// a `SyncPipe`.
+    pub fn try_write(&self, value: V) -> Result<(), WriteError<V>> {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow_mut().try_push(self.source, value),
+            PipeInner::Shared(pipe) => pipe.try_write(value),
+            PipeInner::Broadcast(targets) => {
+                let open: Vec<&Pipe<V>> = targets.iter().filter(|t| !t.is_closed()).collect();
+                if open.is_empty() {
+                    return Err(WriteError::Closed(value));
+                }
+                if open.iter().any(|t| t.is_full()) {
+                    return Err(WriteError::Full(value));
+                }
+                for target in open {
+                    target.write(value.clone());
+                }
+                Ok(())
+            }
+            PipeInner::Tee(tee) => {
+                tee.0.try_write(value.clone())?;
+                tee.1.write(value);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn peek(&self) -> Option<V> {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow().values.front().cloned(),
+            PipeInner::Shared(pipe) => pipe.peek(),
+            PipeInner::Broadcast(_) => None,
+            PipeInner::Tee(tee) => tee.0.peek(),
+        }
+    }
+
+    /// The values currently in the pipe, without reading them.
+    pub fn contents(&self) -> Vec<V> {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow().values.iter().cloned().collect(),
+            PipeInner::Shared(pipe) => pipe.contents(),
+            PipeInner::Broadcast(_) => Vec::new(),
+            PipeInner::Tee(tee) => tee.0.contents(),
+        }
+    }
+
+    /// Closes the pipe: no more values can be written to it, and the values it holds can still
+    /// be read. Closing a broadcast pipe or a tee closes its targets, but not the log of a tee.
+    pub fn close(&self) {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow_mut().closed = true,
+            PipeInner::Shared(pipe) => pipe.close(),
+            PipeInner::Broadcast(targets) => targets.iter().for_each(Pipe::close),
+            PipeInner::Tee(tee) => tee.0.close(),
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow().closed,
+            PipeInner::Shared(pipe) => pipe.is_closed(),
+            PipeInner::Broadcast(targets) => targets.iter().all(Pipe::is_closed),
+            PipeInner::Tee(tee) => tee.0.is_closed(),
+        }
+    }
+
+    /// Whether the pipe is closed and all its values were read.
+    pub fn is_finished(&self) -> bool {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow().is_finished(),
+            PipeInner::Shared(pipe) => pipe.is_finished(),
+            PipeInner::Broadcast(_) => self.is_closed(),
+            PipeInner::Tee(tee) => tee.0.is_finished(),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow().is_full(),
+            PipeInner::Shared(pipe) => pipe.is_full(),
+            PipeInner::Broadcast(targets) => targets.iter().any(Pipe::is_full),
+            PipeInner::Tee(tee) => tee.0.is_full(),
+        }
+    }
+
+    /// The number of values the pipe can hold, if it is bounded.
+    pub fn capacity(&self) -> Option<usize> {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow().capacity,
+            PipeInner::Shared(pipe) => pipe.capacity(),
+            PipeInner::Broadcast(_) => None,
+            PipeInner::Tee(tee) => tee.0.capacity(),
+        }
+    }
+
+    pub fn stats(&self) -> PipeStats {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow().stats,
+            PipeInner::Shared(pipe) => pipe.stats(),
+            PipeInner::Broadcast(_) => PipeStats::default(),
+            PipeInner::Tee(tee) => tee.0.stats(),
+        }
+    }
+
+    /// Creates a pipe of the same kind holding a copy of the values of this one, but not shared
+    /// with it. The targets of a broadcast pipe or a tee are forked as well.
+    pub fn fork(&self) -> Self {
+        let inner = match &self.inner {
+            PipeInner::Local(queue) => {
+                PipeInner::Local(Rc::new(RefCell::new(queue.borrow().clone())))
+            }
+            PipeInner::Shared(pipe) => PipeInner::Shared(pipe.fork()),
+            PipeInner::Broadcast(targets) => {
+                PipeInner::Broadcast(targets.iter().map(Pipe::fork).collect())
+            }
+            PipeInner::Tee(tee) => PipeInner::Tee(Rc::new((tee.0.fork(), tee.1.fork()))),
+        };
+        Pipe {
+            inner,
+            source: self.source,
+        }
+    }
+
+    /// Replaces the values in the pipe. A broadcast pipe holds no values to replace.
+    pub(super) fn set_contents(&self, values: Vec<V>) {
+        match &self.inner {
+            PipeInner::Local(queue) => queue.borrow_mut().set_values(values),
+            PipeInner::Shared(pipe) => pipe.set_contents(values),
+            PipeInner::Broadcast(_) => (),
+            PipeInner::Tee(tee) => tee.0.set_contents(values),
+        }
+    }
+}
+
+impl<V: Value> Default for Pipe<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Value> From<SyncPipe<V>> for Pipe<V> {
+    fn from(pipe: SyncPipe<V>) -> Self {
+        Pipe::from_inner(PipeInner::Shared(pipe))
+    }
+}
+
+/// A pipe that can be shared between threads, so that each program of a network runs on its own
+/// thread.
+///
+/// Reads block until a value is written or the pipe is closed, and writes to a bounded pipe until
+/// there is room for the value. Reads and `try_write` give up once the timeout elapses if one is
+/// set. Programs use it once converted into a `Pipe`, and must be created on the thread running
+/// them.
+#[derive(Clone)]
+pub struct SyncPipe<V = i64> {
+    /// The values, and a condition notified each time they change.
+    shared: Arc<(Mutex<Queue<V>>, Condvar)>,
+    timeout: Option<Duration>,
+    source: Option<usize>,
+}
+
+impl<V: Value> SyncPipe<V> {
+    /// Creates a pipe whose reads wait for a value as long as needed.
+    pub fn new() -> Self {
+        Self::create(None, None)
+    }
+
+    /// Creates a pipe whose reads give up after the given timeout.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::create(None, Some(timeout))
+    }
+
+    /// Creates a pipe holding at most `capacity` values, whose reads give up after the timeout
+    /// if there is one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is 0.
+    pub fn bounded(capacity: usize, timeout: Option<Duration>) -> Self {
+        Self::create(Some(capacity), timeout)
+    }
+
+    fn create(capacity: Option<usize>, timeout: Option<Duration>) -> Self {
+        SyncPipe {
+            shared: Arc::new((Mutex::new(Queue::new(capacity)), Condvar::new())),
+            timeout,
+            source: None,
+        }
+    }
+
+    /// A pipe writing into this one, the values it writes tagged with the given source, like
+    /// `Pipe::source`.
+    pub fn source(&self, source: usize) -> Self {
+        self.lock().track_sources();
+        SyncPipe {
+            source: Some(source),
+            ..self.clone()
+        }
+    }
+
+    /// Reads the next value, waiting for it within the timeout of the pipe. Returns `None` at
+    /// once if the pipe is finished.
+    pub fn read(&self) -> Option<V> {
+        self.read_tagged().map(|(_, value)| value)
+    }
+
+    /// Reads the next value like `read`, with the source it was written through, if any.
+    pub fn read_tagged(&self) -> Option<(Option<usize>, V)> {
+        match self.timeout {
+            Some(timeout) => self.read_tagged_timeout(timeout),
+            None => {
+                let (_, changed) = &*self.shared;
+                let mut queue = changed
+                    .wait_while(self.lock(), |q| q.values.is_empty() && !q.closed)
+                    .unwrap_or_else(PoisonError::into_inner);
+                self.notify(queue.pop_tagged())
+            }
+        }
+    }
+
+    /// Reads the next value, waiting for it at most the given duration.
+    pub fn read_timeout(&self, timeout: Duration) -> Option<V> {
+        self.read_tagged_timeout(timeout).map(|(_, value)| value)
+    }
+
+    fn read_tagged_timeout(&self, timeout: Duration) -> Option<(Option<usize>, V)> {
+        let (_, changed) = &*self.shared;
+        let (mut queue, _) = changed
+            .wait_timeout_while(self.lock(), timeout, |q| q.values.is_empty() && !q.closed)
+            .unwrap_or_else(PoisonError::into_inner);
+        self.notify(queue.pop_tagged())
+    }
+
+    /// Reads the next value if there is one, without waiting.
+    pub fn try_read(&self) -> Option<V> {
+        let value = self.lock().pop();
+        self.notify(value)
+    }
+
+    /// Writes a value, waiting for room in a bounded pipe as long as needed. Values written to a
+    /// closed pipe are dropped.
+    pub fn write(&self, value: V) {
+        let (_, changed) = &*self.shared;
+        changed
+            .wait_while(self.lock(), |q| q.is_full() && !q.closed)
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(self.source, value);
+        changed.notify_all();
+    }
+
+    /// Writes a value unless the pipe is closed, or still full once its timeout elapsed.
+    pub fn try_write(&self, value: V) -> Result<(), WriteError<V>> {
+        let (_, changed) = &*self.shared;
+        let full = |q: &mut Queue<V>| q.is_full() && !q.closed;
+        let mut queue = match self.timeout {
+            Some(timeout) => {
+                changed
+                    .wait_timeout_while(self.lock(), timeout, full)
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0
+            }
+            None => changed
+                .wait_while(self.lock(), full)
+                .unwrap_or_else(PoisonError::into_inner),
+        };
+        queue.try_push(self.source, value)?;
+        changed.notify_all();
+        Ok(())
+    }
+
+    pub fn peek(&self) -> Option<V> {
+        self.lock().values.front().cloned()
+    }
+
+    /// The values currently in the pipe, without reading them.
+    pub fn contents(&self) -> Vec<V> {
+        self.lock().values.iter().cloned().collect()
+    }
+
+    /// Closes the pipe, waking up the threads waiting on it.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        let (_, changed) = &*self.shared;
+        changed.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Whether the pipe is closed and all its values were read.
+    pub fn is_finished(&self) -> bool {
+        self.lock().is_finished()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.lock().is_full()
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.lock().capacity
+    }
+
+    pub fn stats(&self) -> PipeStats {
+        self.lock().stats
+    }
+
+    fn fork(&self) -> Self {
+        SyncPipe {
+            shared: Arc::new((Mutex::new(self.lock().clone()), Condvar::new())),
+            timeout: self.timeout,
+            source: self.source,
+        }
+    }
+
+    fn set_contents(&self, values: Vec<V>) {
+        self.lock().set_values(values);
+        let (_, changed) = &*self.shared;
+        changed.notify_all();
+    }
+
+    /// Wakes up the writers waiting for room after a read.
+    fn notify<T>(&self, value: Option<T>) -> Option<T> {
+        if value.is_some() {
+            let (_, changed) = &*self.shared;
+            changed.notify_all();
+        }
+        value
+    }
+
+    // A panic while holding the lock cannot leave the queue half updated, so a poisoned lock is
+    // still used.
+    fn lock(&self) -> MutexGuard<'_, Queue<V>> {
+        let (queue, _) = &*self.shared;
+        queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<V: Value> Default for SyncPipe<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod pipe_should {
+    use super::*;
+    use crate::intcode::{Intcode, IntcodeErrorKind, Program};
+
+    #[test]
+    fn read_a_value_written_to_one_of_its_clones() {
+        let pipe = Pipe::new();
+        let clone = pipe.clone();
+
+        clone.write(33);
+        let result = pipe.read();
+
+        assert_eq!(result, Some(33));
+    }
+
+    #[test]
+    fn not_share_its_values_with_a_fork() {
+        let pipe = Pipe::new();
+        pipe.write(1);
+
+        let fork = pipe.fork();
+        fork.write(2);
+        pipe.read();
+
+        assert_eq!(pipe.contents(), vec![]);
+        assert_eq!(fork.contents(), vec![1, 2]);
+    }
+
+    #[test]
+    fn refuse_a_value_once_full_or_closed() {
+        let pipe = Pipe::bounded(2);
+        pipe.write(1);
+
+        let room = pipe.try_write(2);
+        let full = pipe.try_write(3);
+        pipe.close();
+        pipe.read();
+        let closed = pipe.try_write(4);
+
+        assert_eq!(room, Ok(()));
+        assert_eq!(full, Err(WriteError::Full(3)));
+        assert_eq!(closed, Err(WriteError::Closed(4)));
+        assert_eq!(pipe.contents(), vec![2]);
+    }
+
+    #[test]
+    fn be_finished_once_closed_and_emptied() {
+        let pipe = Pipe::new();
+        pipe.write(1);
+        pipe.close();
+        pipe.write(2);
+
+        let closed = pipe.is_finished();
+        let value = pipe.read();
+
+        assert!(!closed);
+        assert_eq!(value, Some(1));
+        assert!(pipe.is_finished());
+    }
+
+    #[test]
+    fn count_the_values_going_through_it() {
+        let pipe = Pipe::new();
+        pipe.write(1);
+        pipe.write(2);
+        pipe.read();
+        pipe.write(3);
+        pipe.write(4);
+
+        assert_eq!(
+            pipe.stats(),
+            PipeStats {
+                written: 4,
+                read: 1,
+                high_water_mark: 3
+            }
+        );
+    }
+
+    #[test]
+    fn make_a_program_writing_to_it_wait_while_full() {
+        let mut program = Program::new(Intcode::from(vec![104, 1, 104, 2, 104, 3, 99]));
+        let output = Pipe::bounded(2);
+        program.set_output(&output);
+
+        let first = program.run().unwrap();
+        output.read();
+        let second = program.run().unwrap();
+
+        assert!(!first);
+        assert!(second);
+        assert_eq!(output.contents(), vec![2, 3]);
+        assert_eq!(output.stats().high_water_mark, 2);
+    }
+
+    #[test]
+    fn keep_values_written_past_its_capacity_outside_a_program() {
+        let pipe = Pipe::bounded(1);
+
+        pipe.write(1);
+        pipe.write(2);
+        let refused = pipe.try_write(3);
+
+        assert_eq!(pipe.contents(), vec![1, 2]);
+        assert!(refused.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "The capacity of a pipe must not be 0")]
+    fn reject_a_capacity_of_0() {
+        Pipe::<i64>::bounded(0);
+    }
+
+    #[test]
+    fn make_a_program_reading_past_its_end_fault() {
+        let mut program = Program::new(Intcode::from(vec![3, 0, 3, 0, 99]));
+        program.write(1);
+        program.input().close();
+
+        let result = program.run();
+
+        assert_eq!(result.unwrap_err().kind, IntcodeErrorKind::EndOfInput);
+        assert_eq!(program.state.current_position, 2);
+    }
+
+    #[test]
+    fn broadcast_the_values_of_a_program_to_independent_readers() {
+        let mut program = Program::new(Intcode::from(vec![104, 1, 104, 2, 99]));
+        let (first, second) = (Pipe::new(), Pipe::new());
+        program.set_output(&Pipe::broadcast(&[first.clone(), second.clone()]));
+
+        program.run().unwrap();
+        first.read();
+
+        assert_eq!(first.contents(), vec![2]);
+        assert_eq!(second.contents(), vec![1, 2]);
+        assert_eq!(program.output().unwrap().contents(), vec![]);
+    }
+
+    #[test]
+    fn make_a_program_wait_while_one_of_its_broadcast_targets_is_full() {
+        let mut program = Program::new(Intcode::from(vec![104, 1, 104, 2, 99]));
+        let (bounded, unbounded) = (Pipe::bounded(1), Pipe::new());
+        program.set_output(&Pipe::broadcast(&[bounded.clone(), unbounded.clone()]));
+
+        let first = program.run().unwrap();
+        bounded.read();
+        let second = program.run().unwrap();
+
+        assert!(!first);
+        assert!(second);
+        assert_eq!(bounded.contents(), vec![2]);
+        assert_eq!(unbounded.contents(), vec![1, 2]);
+    }
+
+    #[test]
+    fn merge_the_values_of_several_programs_with_their_source() {
+        let merged = Pipe::new();
+        merged.write(0);
+        let mut programs: Vec<Program> = (1..=2)
+            .map(|i| {
+                let mut program = Program::new(Intcode::from(vec![104, i * 10, 99]));
+                program.set_output(&merged.source(i as usize));
+                program
+            })
+            .collect();
+
+        programs[1].run().unwrap();
+        programs[0].run().unwrap();
+
+        assert_eq!(merged.read_tagged(), Some((None, 0)));
+        assert_eq!(merged.read_tagged(), Some((Some(2), 20)));
+        assert_eq!(merged.read(), Some(10));
+        assert_eq!(merged.read_tagged(), None);
+    }
+
+    #[test]
+    fn copy_the_values_written_through_a_tee_to_its_log() {
+        let mut program = Program::new(Intcode::from(vec![104, 1, 104, 2, 99]));
+        let (target, log) = (Pipe::new(), Pipe::new());
+        let tee = Pipe::tee(&target, &log);
+        program.set_output(&tee);
+
+        program.run().unwrap();
+        let read = tee.read();
+
+        assert_eq!(read, Some(1));
+        assert_eq!(target.contents(), vec![2]);
+        assert_eq!(log.contents(), vec![1, 2]);
+    }
+
+    #[test]
+    fn make_a_program_writing_to_it_once_closed_fault() {
+        let mut program = Program::new(Intcode::from(vec![104, 1, 99]));
+        let output = Pipe::new();
+        output.close();
+        program.set_output(&output);
+
+        let result = program.run();
+
+        assert_eq!(result.unwrap_err().kind, IntcodeErrorKind::ClosedOutput);
+    }
+}
+
+#[cfg(test)]
+mod sync_pipe_should {
+    use super::*;
+    use crate::intcode::{Intcode, Program};
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn wait_for_a_value_written_by_another_thread() {
+        let pipe = SyncPipe::new();
+        let writer = pipe.clone();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            writer.write(42);
+        });
+
+        assert_eq!(pipe.read(), Some(42));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn give_up_reading_after_its_timeout() {
+        let pipe: SyncPipe = SyncPipe::with_timeout(Duration::from_millis(20));
+        let start = Instant::now();
+
+        let result = pipe.read();
+
+        assert_eq!(result, None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(pipe.try_read(), None);
+    }
+
+    #[test]
+    fn make_its_writer_wait_for_room() {
+        let pipe = SyncPipe::bounded(1, None);
+        let writer = pipe.clone();
+
+        let handle = thread::spawn(move || {
+            writer.write(1);
+            writer.write(2);
+        });
+        thread::sleep(Duration::from_millis(20));
+        let waiting = pipe.contents();
+
+        assert_eq!(waiting, vec![1]);
+        assert_eq!(pipe.read(), Some(1));
+        handle.join().unwrap();
+        assert_eq!(pipe.contents(), vec![2]);
+    }
+
+    #[test]
+    fn wake_its_reader_up_when_closed() {
+        let pipe: SyncPipe = SyncPipe::new();
+        let closer = pipe.clone();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            closer.close();
+        });
+
+        assert_eq!(pipe.read(), None);
+        assert!(pipe.is_finished());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn tag_the_values_written_from_other_threads_with_their_source() {
+        let merged: SyncPipe = SyncPipe::new();
+
+        let handles: Vec<_> = (0..3)
+            .map(|source| {
+                let writer = merged.source(source);
+                thread::spawn(move || writer.write(source as i64 * 10))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let mut values: Vec<_> = (0..3).map(|_| merged.read_tagged().unwrap()).collect();
+        values.sort();
+
+        assert_eq!(values, vec![(Some(0), 0), (Some(1), 10), (Some(2), 20)]);
+    }
+
+    #[test]
+    fn run_a_feedback_loop_of_programs_on_separate_threads() {
+        let code = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let settings = [9, 8, 7, 6, 5];
+        let pipes: Vec<SyncPipe> = settings
+            .iter()
+            .map(|s| {
+                let pipe = SyncPipe::with_timeout(Duration::from_secs(5));
+                pipe.write(*s);
+                pipe
+            })
+            .collect();
+        pipes[0].write(0);
+
+        let handles: Vec<_> = (0..pipes.len())
+            .map(|i| {
+                let code = code.clone();
+                let input = pipes[i].clone();
+                let output = pipes[(i + 1) % pipes.len()].clone();
+                thread::spawn(move || {
+                    let mut program = Program::new(Intcode::from(code));
+                    program.set_input(&input.into());
+                    program.set_output(&output.into());
+                    program.run().unwrap()
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+        assert_eq!(pipes[0].contents(), vec![139629729]);
+    }
+}
@@ -0,0 +1,291 @@
+use super::disassembler::Statement;
+use super::trace::{ExecutionObserver, InstructionEvent};
+use super::{OpCode, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display, Formatter};
+
+/// An observer counting where a program spends its time: the instructions executed at each
+/// address and for each opcode, the reads and writes of each memory cell, and the values read
+/// and written.
+///
+/// Like any observer, it is added to a program with `Program::add_observer`, shared in an
+/// `Rc<RefCell<_>>` to read its counts afterwards. Programs without observers are not slowed
+/// down.
+#[derive(Clone, Debug)]
+pub struct Profiler<V = i64> {
+    instructions: usize,
+    addresses: BTreeMap<usize, usize>,
+    /// The last statement executed at each address, which may change if the program modifies
+    /// itself.
+    statements: HashMap<usize, Statement<V>>,
+    opcodes: HashMap<OpCode, usize>,
+    reads: BTreeMap<usize, usize>,
+    writes: BTreeMap<usize, usize>,
+    inputs: usize,
+    outputs: usize,
+}
+
+/// An address executed by a program, with the number of instructions executed there.
+#[derive(Clone, PartialEq, Debug)]
+pub struct HotSpot<V = i64> {
+    pub address: usize,
+    pub count: usize,
+    pub statement: Statement<V>,
+}
+
+impl<V: Value> Profiler<V> {
+    pub fn new() -> Self {
+        Profiler {
+            instructions: 0,
+            addresses: BTreeMap::new(),
+            statements: HashMap::new(),
+            opcodes: HashMap::new(),
+            reads: BTreeMap::new(),
+            writes: BTreeMap::new(),
+            inputs: 0,
+            outputs: 0,
+        }
+    }
+
+    /// The number of instructions executed.
+    pub fn instructions(&self) -> usize {
+        self.instructions
+    }
+
+    /// The number of instructions executed at each address.
+    pub fn address_counts(&self) -> &BTreeMap<usize, usize> {
+        &self.addresses
+    }
+
+    /// The number of instructions executed for each opcode.
+    pub fn opcode_counts(&self) -> &HashMap<OpCode, usize> {
+        &self.opcodes
+    }
+
+    /// The number of reads of each memory cell by the parameters of the instructions.
+    pub fn read_counts(&self) -> &BTreeMap<usize, usize> {
+        &self.reads
+    }
+
+    /// The number of writes to each memory cell.
+    pub fn write_counts(&self) -> &BTreeMap<usize, usize> {
+        &self.writes
+    }
+
+    /// The number of values read from the input.
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// The number of values written to the output.
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    /// The executed addresses, from the most executed one.
+    pub fn hot_spots(&self) -> Vec<HotSpot<V>> {
+        let mut hot_spots: Vec<HotSpot<V>> = self
+            .addresses
+            .iter()
+            .map(|(address, count)| HotSpot {
+                address: *address,
+                count: *count,
+                statement: self.statements[address].clone(),
+            })
+            .collect();
+        hot_spots.sort_by(|a, b| b.count.cmp(&a.count).then(a.address.cmp(&b.address)));
+        hot_spots
+    }
+
+    /// A report of the counts, listing at most `limit` entries in each ranking.
+    pub fn report(&self, limit: usize) -> ProfileReport<'_, V> {
+        ProfileReport {
+            profiler: self,
+            limit,
+        }
+    }
+}
+
+impl<V: Value> Default for Profiler<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Value> ExecutionObserver<V> for Profiler<V> {
+    fn on_instruction(&mut self, event: &InstructionEvent<V>) {
+        self.instructions += 1;
+        *self.addresses.entry(event.address).or_insert(0) += 1;
+        self.statements.insert(event.address, event.statement());
+        *self
+            .opcodes
+            .entry(event.instruction.opcode().clone())
+            .or_insert(0) += 1;
+        for address in &event.reads {
+            *self.reads.entry(*address).or_insert(0) += 1;
+        }
+        if let Some(write) = &event.write {
+            *self.writes.entry(write.address).or_insert(0) += 1;
+        }
+        if event.input.is_some() {
+            self.inputs += 1;
+        }
+        if event.output.is_some() {
+            self.outputs += 1;
+        }
+    }
+}
+
+/// The report of a profiler, ranking the hot spots, the opcodes and the memory cells from the
+/// most used one.
+pub struct ProfileReport<'a, V = i64> {
+    profiler: &'a Profiler<V>,
+    limit: usize,
+}
+
+impl<'a, V: Value> ProfileReport<'a, V> {
+    fn share(&self, count: usize) -> f64 {
+        100.0 * count as f64 / self.profiler.instructions.max(1) as f64
+    }
+
+    fn write_cells(
+        &self,
+        f: &mut Formatter,
+        title: &str,
+        counts: &BTreeMap<usize, usize>,
+    ) -> fmt::Result {
+        writeln!(f)?;
+        writeln!(f, "{}", title)?;
+        let mut cells: Vec<(&usize, &usize)> = counts.iter().collect();
+        cells.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (address, count) in cells.into_iter().take(self.limit) {
+            writeln!(f, "{:>10}  [{}]", count, address)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, V: Value> Display for ProfileReport<'a, V> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let profiler = self.profiler;
+        writeln!(
+            f,
+            "{} instructions executed, {} inputs read, {} outputs written",
+            profiler.instructions, profiler.inputs, profiler.outputs
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Hot spots")?;
+        for hot_spot in profiler.hot_spots().into_iter().take(self.limit) {
+            writeln!(
+                f,
+                "{:>10} {:>6.2}%  {:>6}: {}",
+                hot_spot.count,
+                self.share(hot_spot.count),
+                hot_spot.address,
+                hot_spot.statement
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Opcodes")?;
+        let mut opcodes: Vec<(&OpCode, &usize)> = profiler.opcodes.iter().collect();
+        opcodes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.code().cmp(&b.0.code())));
+        for (opcode, count) in opcodes.into_iter().take(self.limit) {
+            writeln!(
+                f,
+                "{:>10} {:>6.2}%  {}",
+                count,
+                self.share(*count),
+                opcode.mnemonic()
+            )?;
+        }
+        self.write_cells(f, "Memory reads", &profiler.reads)?;
+        self.write_cells(f, "Memory writes", &profiler.writes)
+    }
+}
+
+#[cfg(test)]
+mod profiler_should {
+    use super::*;
+    use crate::intcode::{Intcode, Pipe, Program};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Reads a count and outputs it after adding 1 to 0 as many times.
+    fn count_up() -> Rc<RefCell<Profiler>> {
+        let mut program = Program::new(Intcode::from(vec![
+            3, 17, 1001, 18, 1, 18, 8, 17, 18, 19, 1006, 19, 2, 4, 18, 99, 0, 0, 0, 0,
+        ]));
+        program.set_output(&Pipe::new());
+        program.write(3);
+        let profiler = Rc::new(RefCell::new(Profiler::new()));
+        program.add_observer(Box::new(profiler.clone()));
+        program.run().unwrap();
+        profiler
+    }
+
+    #[test]
+    fn count_the_instructions_executed_at_each_address_and_for_each_opcode() {
+        let profiler = count_up();
+        let profiler = profiler.borrow();
+
+        assert_eq!(profiler.instructions(), 12);
+        assert_eq!(
+            profiler.address_counts().iter().collect::<Vec<_>>(),
+            vec![
+                (&0, &1),
+                (&2, &3),
+                (&6, &3),
+                (&10, &3),
+                (&13, &1),
+                (&15, &1)
+            ]
+        );
+        assert_eq!(profiler.opcode_counts()[&OpCode::Add], 3);
+        assert_eq!(profiler.opcode_counts()[&OpCode::Halt], 1);
+        assert_eq!(profiler.inputs(), 1);
+        assert_eq!(profiler.outputs(), 1);
+    }
+
+    #[test]
+    fn count_the_reads_and_writes_of_each_memory_cell() {
+        let profiler = count_up();
+        let profiler = profiler.borrow();
+
+        assert_eq!(
+            profiler.read_counts().iter().collect::<Vec<_>>(),
+            vec![(&17, &3), (&18, &7), (&19, &3)]
+        );
+        assert_eq!(
+            profiler.write_counts().iter().collect::<Vec<_>>(),
+            vec![(&17, &1), (&18, &3), (&19, &3)]
+        );
+    }
+
+    #[test]
+    fn rank_the_hot_spots_in_its_report() {
+        let profiler = count_up();
+
+        let report = profiler.borrow().report(2).to_string();
+
+        assert_eq!(
+            report,
+            "12 instructions executed, 1 inputs read, 1 outputs written\n\
+             \n\
+             Hot spots\n\
+             \x20        3  25.00%       2: add [18], #1, [18]\n\
+             \x20        3  25.00%       6: eq [17], [18], [19]\n\
+             \n\
+             Opcodes\n\
+             \x20        3  25.00%  add\n\
+             \x20        3  25.00%  jz\n\
+             \n\
+             Memory reads\n\
+             \x20        7  [18]\n\
+             \x20        3  [17]\n\
+             \n\
+             Memory writes\n\
+             \x20        3  [18]\n\
+             \x20        3  [19]\n"
+        );
+    }
+}
@@ -0,0 +1,440 @@
+use super::{Intcode, IntcodeError, IntcodeErrorKind, Memory, Pipe, Program, ProgramStatus, Value};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter, Write};
+
+/// The first line of a saved program, followed by the version of the format.
+const HEADER: &str = "intcode-program";
+const VERSION: u32 = 1;
+
+/// An error found while loading a saved program, with the line (starting at 1) where it was
+/// found.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LoadError {
+    pub line: usize,
+    pub kind: LoadErrorKind,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LoadErrorKind {
+    MissingHeader,
+    UnsupportedVersion(String),
+    UnknownKey(String),
+    DuplicateKey(String),
+    MissingKey(&'static str),
+    /// A key that does not go with the status of the program, such as an error for a program
+    /// that did not fault.
+    UnexpectedKey(&'static str),
+    InvalidValue(String),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            LoadErrorKind::MissingHeader => write!(f, "not a saved intcode program"),
+            LoadErrorKind::UnsupportedVersion(v) => write!(f, "unsupported version '{}'", v),
+            LoadErrorKind::UnknownKey(k) => write!(f, "unknown key '{}'", k),
+            LoadErrorKind::DuplicateKey(k) => write!(f, "key '{}' is already set", k),
+            LoadErrorKind::MissingKey(k) => write!(f, "missing key '{}'", k),
+            LoadErrorKind::UnexpectedKey(k) => {
+                write!(f, "key '{}' does not go with the status", k)
+            }
+            LoadErrorKind::InvalidValue(v) => write!(f, "invalid value '{}'", v),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Whether the text starts like a saved program.
+pub fn is_saved_program(source: &str) -> bool {
+    source.trim_start().starts_with(HEADER)
+}
+
+impl<V: Value, M: Memory<V>> Program<V, M> {
+    /// Saves the program to text: its memory, instruction pointer, relative base and status,
+    /// with the error it faulted with, and the values waiting in its pipes. The observers, the
+    /// instruction set and the execution settings of the program are not saved.
+    ///
+    /// The text starts with a versioned header, followed by one `key value` line for each part:
+    ///
+    /// ```text
+    /// intcode-program 1
+    /// status waiting
+    /// ip 0
+    /// relative-base 0
+    /// memory 3,15,1006,15,14
+    /// cell 1000000 7
+    /// input
+    /// output 4,9
+    /// ```
+    ///
+    /// `memory` lists the loaded program and the memory it grew into, and each `cell` line a
+    /// value written further away. `output` is missing if the program has no output.
+    pub fn save(&self) -> String {
+        let state = &self.state;
+        let mut text = String::new();
+        writeln!(text, "{} {}", HEADER, VERSION).unwrap();
+        writeln!(text, "status {}", status_name(&state.status)).unwrap();
+        writeln!(text, "ip {}", state.current_position).unwrap();
+        writeln!(text, "relative-base {}", state.relative_base).unwrap();
+        let memory: Vec<V> = (0..state.intcode.len())
+            .map(|p| state.intcode.read(p))
+            .collect();
+        writeln!(text, "memory {}", join(&memory)).unwrap();
+        for (address, value) in state.intcode.memory().cells() {
+            if address >= state.intcode.len() && value != V::from(0) {
+                writeln!(text, "cell {} {}", address, value).unwrap();
+            }
+        }
+        writeln!(text, "input {}", join(&self.input.contents())).unwrap();
+        if let Some(output) = &self.output {
+            writeln!(text, "output {}", join(&output.contents())).unwrap();
+        }
+        if let Some(error) = &self.error {
+            writeln!(
+                text,
+                "error {} {} {}",
+                error.instruction_pointer,
+                error.instruction,
+                error_kind_text(&error.kind)
+            )
+            .unwrap();
+        }
+        text.lines()
+            .map(|l| l.trim_end().to_string() + "\n")
+            .collect()
+    }
+
+    /// Loads a program saved by `save`, with new pipes holding the values of the saved ones.
+    pub fn load(source: &str) -> Result<Self, LoadError> {
+        let mut lines = source
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty());
+        match lines.next() {
+            Some((line, text)) => match split_key(text) {
+                (HEADER, version) if version == VERSION.to_string() => (),
+                (HEADER, version) => {
+                    return Err(LoadError {
+                        line,
+                        kind: LoadErrorKind::UnsupportedVersion(version.to_string()),
+                    })
+                }
+                _ => return Err(error(line, LoadErrorKind::MissingHeader)),
+            },
+            None => return Err(error(1, LoadErrorKind::MissingHeader)),
+        }
+        let mut saved = SavedProgram::new();
+        let mut last_line = 1;
+        let mut error_line = None;
+        for (line, text) in lines {
+            last_line = line;
+            saved.parse_line(text).map_err(|kind| error(line, kind))?;
+            if saved.error.is_some() && error_line.is_none() {
+                error_line = Some(line);
+            }
+        }
+        let missing = |key| error(last_line + 1, LoadErrorKind::MissingKey(key));
+        let status = saved.status.ok_or_else(|| missing("status"))?;
+        match (&status, error_line) {
+            (ProgramStatus::Faulted, None) => return Err(missing("error")),
+            (ProgramStatus::Faulted, Some(_)) | (_, None) => (),
+            (_, Some(line)) => return Err(error(line, LoadErrorKind::UnexpectedKey("error"))),
+        }
+        let memory = saved.memory.ok_or_else(|| missing("memory"))?;
+        let mut intcode = Intcode::from(memory).with_memory::<M>();
+        for (address, value) in saved.cells {
+            intcode.write(address, value);
+        }
+        let mut program = Program::new(intcode);
+        program.state.status = status;
+        program.state.current_position = saved.ip.ok_or_else(|| missing("ip"))?;
+        program.state.relative_base = saved
+            .relative_base
+            .ok_or_else(|| missing("relative-base"))?;
+        program.input.set_contents(saved.input.unwrap_or_default());
+        if let Some(values) = saved.output {
+            let output = Pipe::new();
+            output.set_contents(values);
+            program.output = Some(output);
+        }
+        program.error = saved.error;
+        Ok(program)
+    }
+}
+
+struct SavedProgram<V> {
+    status: Option<ProgramStatus>,
+    ip: Option<usize>,
+    relative_base: Option<V>,
+    memory: Option<Vec<V>>,
+    cells: Vec<(usize, V)>,
+    input: Option<Vec<V>>,
+    output: Option<Vec<V>>,
+    error: Option<IntcodeError<V>>,
+}
+
+impl<V: Value> SavedProgram<V> {
+    fn new() -> Self {
+        SavedProgram {
+            status: None,
+            ip: None,
+            relative_base: None,
+            memory: None,
+            cells: Vec::new(),
+            input: None,
+            output: None,
+            error: None,
+        }
+    }
+
+    fn parse_line(&mut self, text: &str) -> Result<(), LoadErrorKind> {
+        let (key, value) = split_key(text);
+        match key {
+            "status" => set(&mut self.status, key, parse_status(value)?),
+            "ip" => set(&mut self.ip, key, parse(value)?),
+            "relative-base" => set(&mut self.relative_base, key, parse(value)?),
+            "memory" => set(&mut self.memory, key, parse_list(value)?),
+            "cell" => {
+                let (address, value) = split_key(value);
+                self.cells.push((parse(address)?, parse(value)?));
+                Ok(())
+            }
+            "input" => set(&mut self.input, key, parse_list(value)?),
+            "output" => set(&mut self.output, key, parse_list(value)?),
+            "error" => {
+                let error = parse_error(value)?;
+                set(&mut self.error, key, error)
+            }
+            _ => Err(LoadErrorKind::UnknownKey(key.to_string())),
+        }
+    }
+}
+
+fn error(line: usize, kind: LoadErrorKind) -> LoadError {
+    LoadError { line, kind }
+}
+
+fn split_key(text: &str) -> (&str, &str) {
+    match text.find(char::is_whitespace) {
+        Some(index) => (&text[..index], text[index..].trim()),
+        None => (text, ""),
+    }
+}
+
+fn set<T>(field: &mut Option<T>, key: &str, value: T) -> Result<(), LoadErrorKind> {
+    match field {
+        Some(_) => Err(LoadErrorKind::DuplicateKey(key.to_string())),
+        None => {
+            *field = Some(value);
+            Ok(())
+        }
+    }
+}
+
+fn parse<T: std::str::FromStr>(text: &str) -> Result<T, LoadErrorKind> {
+    text.parse()
+        .map_err(|_| LoadErrorKind::InvalidValue(text.to_string()))
+}
+
+fn parse_list<V: Value>(text: &str) -> Result<Vec<V>, LoadErrorKind> {
+    match text.is_empty() {
+        true => Ok(Vec::new()),
+        false => text.split(',').map(|v| parse(v.trim())).collect(),
+    }
+}
+
+fn join<V: Value>(values: &[V]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    values.join(",")
+}
+
+fn status_name(status: &ProgramStatus) -> &'static str {
+    match status {
+        ProgramStatus::Running => "running",
+        ProgramStatus::Waiting => "waiting",
+        ProgramStatus::BudgetExhausted => "budget-exhausted",
+        ProgramStatus::Over => "over",
+        ProgramStatus::Faulted => "faulted",
+    }
+}
+
+fn parse_status(text: &str) -> Result<ProgramStatus, LoadErrorKind> {
+    match text {
+        "running" => Ok(ProgramStatus::Running),
+        "waiting" => Ok(ProgramStatus::Waiting),
+        "budget-exhausted" => Ok(ProgramStatus::BudgetExhausted),
+        "over" => Ok(ProgramStatus::Over),
+        "faulted" => Ok(ProgramStatus::Faulted),
+        _ => Err(LoadErrorKind::InvalidValue(text.to_string())),
+    }
+}
+
+fn error_kind_text<V: Value>(kind: &IntcodeErrorKind<V>) -> String {
+    match kind {
+        IntcodeErrorKind::UnknownOpcode => "unknown-opcode".to_string(),
+        IntcodeErrorKind::InvalidAddress(address) => format!("invalid-address {}", address),
+        IntcodeErrorKind::InvalidParameterMode(mode) => format!("invalid-parameter-mode {}", mode),
+        IntcodeErrorKind::WriteInImmediateMode => "write-in-immediate-mode".to_string(),
+        IntcodeErrorKind::InfiniteLoop => "infinite-loop".to_string(),
+        IntcodeErrorKind::EndOfInput => "end-of-input".to_string(),
+        IntcodeErrorKind::ClosedOutput => "closed-output".to_string(),
+    }
+}
+
+fn parse_error<V: Value>(text: &str) -> Result<IntcodeError<V>, LoadErrorKind> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let invalid = || LoadErrorKind::InvalidValue(text.to_string());
+    let kind = match words.get(2..).ok_or_else(invalid)? {
+        ["unknown-opcode"] => IntcodeErrorKind::UnknownOpcode,
+        ["invalid-address", address] => IntcodeErrorKind::InvalidAddress(parse(address)?),
+        ["invalid-parameter-mode", mode] => IntcodeErrorKind::InvalidParameterMode(parse(mode)?),
+        ["write-in-immediate-mode"] => IntcodeErrorKind::WriteInImmediateMode,
+        ["infinite-loop"] => IntcodeErrorKind::InfiniteLoop,
+        ["end-of-input"] => IntcodeErrorKind::EndOfInput,
+        ["closed-output"] => IntcodeErrorKind::ClosedOutput,
+        _ => return Err(invalid()),
+    };
+    Ok(IntcodeError {
+        kind,
+        instruction_pointer: parse(words[0])?,
+        instruction: parse(words[1])?,
+    })
+}
+
+#[cfg(test)]
+mod save_should {
+    use super::*;
+    use crate::intcode::{squares, SparseMemory};
+
+    fn program() -> Program {
+        let mut program = Program::new(squares());
+        program.set_output(&Pipe::new());
+        program
+    }
+
+    #[test]
+    fn save_a_program_waiting_for_input() {
+        let mut program = program();
+        program.write(3);
+        program.run().unwrap();
+        program.state.intcode.write(1_000_000, 7);
+        program.write(4);
+
+        assert_eq!(
+            program.save(),
+            "intcode-program 1\n\
+             status waiting\n\
+             ip 0\n\
+             relative-base 0\n\
+             memory 3,15,1006,15,14,2,15,15,16,4,16,1105,1,0,99,3,9\n\
+             cell 1000000 7\n\
+             input 4\n\
+             output 9\n"
+        );
+    }
+
+    #[test]
+    fn resume_a_loaded_program() {
+        let mut program = program();
+        program.write(3);
+        program.run().unwrap();
+        program.write(4);
+
+        let mut loaded: Program = Program::load(&program.save()).unwrap();
+        loaded.write(0);
+
+        assert!(loaded.run().unwrap());
+        assert_eq!(loaded.output().unwrap().contents(), vec![9, 16]);
+    }
+
+    #[test]
+    fn load_a_faulted_program_with_its_error() {
+        let mut program: Program = Program::new(Intcode::from(vec![109, -5, 204, 1]));
+        program.run().unwrap_err();
+
+        let mut loaded: Program<i64, SparseMemory> = Program::load(&program.save()).unwrap();
+
+        assert_eq!(loaded.status(), ProgramStatus::Faulted);
+        assert_eq!(
+            loaded.run().unwrap_err().kind,
+            IntcodeErrorKind::InvalidAddress(-4)
+        );
+        assert_eq!(loaded.save(), program.save());
+    }
+
+    #[test]
+    fn reject_a_faulted_program_without_its_error() {
+        let mut program: Program = Program::new(Intcode::from(vec![109, -5, 204, 1]));
+        program.run().unwrap_err();
+        let source: String = program
+            .save()
+            .lines()
+            .filter(|l| !l.starts_with("error"))
+            .map(|l| format!("{}\n", l))
+            .collect();
+
+        let result: Result<Program, _> = Program::load(&source);
+
+        assert_eq!(
+            result.err(),
+            Some(LoadError {
+                line: 7,
+                kind: LoadErrorKind::MissingKey("error")
+            })
+        );
+    }
+
+    #[test]
+    fn reject_an_error_for_a_program_that_did_not_fault() {
+        let mut program: Program = Program::new(Intcode::from(vec![109, -5, 204, 1]));
+        program.run().unwrap_err();
+        let source = program.save().replace("status faulted", "status over");
+
+        let result: Result<Program, _> = Program::load(&source);
+
+        assert_eq!(
+            result.err().map(|e| e.to_string()),
+            Some("line 7: key 'error' does not go with the status".to_string())
+        );
+    }
+
+    #[test]
+    fn reject_another_version_of_the_format() {
+        let result: Result<Program, _> = Program::load("intcode-program 2\nip 0\n");
+
+        assert_eq!(
+            result.err(),
+            Some(LoadError {
+                line: 1,
+                kind: LoadErrorKind::UnsupportedVersion("2".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn report_the_line_of_an_invalid_value() {
+        let result: Result<Program, _> =
+            Program::load("intcode-program 1\nstatus over\nip x\nrelative-base 0\nmemory 99\n");
+
+        assert_eq!(
+            result.err().map(|e| e.to_string()),
+            Some("line 3: invalid value 'x'".to_string())
+        );
+    }
+
+    #[test]
+    fn report_a_missing_key() {
+        let result: Result<Program, _> = Program::load("intcode-program 1\nstatus over\nip 0\n");
+
+        assert_eq!(
+            result.err(),
+            Some(LoadError {
+                line: 4,
+                kind: LoadErrorKind::MissingKey("memory")
+            })
+        );
+    }
+}
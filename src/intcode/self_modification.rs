@@ -0,0 +1,173 @@
+use super::trace::{ExecutionObserver, InstructionEvent};
+use super::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+/// A write of a program into its own instructions.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct SelfModification {
+    /// The address of the instruction writing.
+    pub writer: usize,
+    /// The address written, part of an instruction.
+    pub target: usize,
+    pub kind: ModificationKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ModificationKind {
+    /// The target was part of an instruction executed before the write.
+    WroteExecuted,
+    /// The target was part of an instruction executed after the write.
+    ExecutedWritten,
+}
+
+impl Display for SelfModification {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.kind {
+            ModificationKind::WroteExecuted => write!(
+                f,
+                "instruction at {} wrote to {}, already executed",
+                self.writer, self.target
+            ),
+            ModificationKind::ExecutedWritten => write!(
+                f,
+                "instruction at {} wrote to {}, executed afterwards",
+                self.writer, self.target
+            ),
+        }
+    }
+}
+
+/// An observer detecting the writes of a program into its own instructions: the words of the
+/// instructions it executes, opcode and parameters, that are written before or after.
+///
+/// Each pair of writer and target is reported once for each kind. Writes made to the memory
+/// outside of the program, such as the noun and verb of day 2, are not reported.
+#[derive(Clone, Default, Debug)]
+pub struct SelfModificationDetector {
+    executed: HashSet<usize>,
+    /// The address of the last instruction writing to each address.
+    writers: HashMap<usize, usize>,
+    modifications: BTreeSet<SelfModification>,
+}
+
+impl SelfModificationDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The writes detected so far, by writer and target address.
+    pub fn modifications(&self) -> Vec<SelfModification> {
+        self.modifications.iter().cloned().collect()
+    }
+}
+
+impl<V: Value> ExecutionObserver<V> for SelfModificationDetector {
+    fn on_instruction(&mut self, event: &InstructionEvent<V>) {
+        for address in event.address..=event.address + event.parameters.len() {
+            if let Some(writer) = self.writers.get(&address) {
+                self.modifications.insert(SelfModification {
+                    writer: *writer,
+                    target: address,
+                    kind: ModificationKind::ExecutedWritten,
+                });
+            }
+            self.executed.insert(address);
+        }
+        if let Some(write) = &event.write {
+            if self.executed.contains(&write.address) {
+                self.modifications.insert(SelfModification {
+                    writer: event.address,
+                    target: write.address,
+                    kind: ModificationKind::WroteExecuted,
+                });
+            }
+            self.writers.insert(write.address, event.address);
+        }
+    }
+}
+
+#[cfg(test)]
+mod self_modification_detector_should {
+    use super::*;
+    use crate::intcode::{Intcode, Pipe, Program};
+
+    fn detect(code: Vec<i64>) -> Vec<SelfModification> {
+        let mut program = Program::new(Intcode::from(code));
+        program.set_output(&Pipe::new());
+        program.set_self_modification_detection(true);
+        program.run().unwrap();
+        program.self_modifications()
+    }
+
+    #[test]
+    fn report_an_instruction_executed_after_being_written() {
+        let result = detect(vec![1101, 100, -1, 4, 0, 0, 0, 0, 99]);
+
+        assert_eq!(
+            result,
+            vec![SelfModification {
+                writer: 0,
+                target: 4,
+                kind: ModificationKind::ExecutedWritten,
+            }]
+        );
+        assert_eq!(
+            result[0].to_string(),
+            "instruction at 0 wrote to 4, executed afterwards"
+        );
+    }
+
+    #[test]
+    fn not_make_a_program_fault_on_an_unused_jump_target() {
+        // A jump not taken, whose target points at an invalid address.
+        let result = detect(vec![105, 0, -1, 99]);
+
+        assert_eq!(result, vec![]);
+    }
+
+    #[test]
+    fn report_a_write_to_an_instruction_already_executed() {
+        let result = detect(vec![1101, 1, 2, 1, 99]);
+
+        assert_eq!(
+            result,
+            vec![SelfModification {
+                writer: 0,
+                target: 1,
+                kind: ModificationKind::WroteExecuted,
+            }]
+        );
+    }
+
+    #[test]
+    fn report_each_writer_and_target_once() {
+        // Rewrites the parameter of its output instruction in a loop, outputting 1, 2 and 3.
+        let result = detect(vec![
+            1001, 5, 1, 5, 104, 0, 1007, 5, 3, 15, 1005, 15, 0, 99, 0, 0,
+        ]);
+
+        assert_eq!(
+            result,
+            vec![
+                SelfModification {
+                    writer: 0,
+                    target: 5,
+                    kind: ModificationKind::WroteExecuted,
+                },
+                SelfModification {
+                    writer: 0,
+                    target: 5,
+                    kind: ModificationKind::ExecutedWritten,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn not_report_writes_to_data() {
+        let result = detect(vec![1101, 1, 2, 5, 99, 0]);
+
+        assert!(result.is_empty());
+    }
+}
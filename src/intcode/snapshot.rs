@@ -0,0 +1,128 @@
+use super::{IntcodeError, Memory, PersistentMemory, Program, ProgramState, Value};
+
+/// The state of a program at some point of its execution, with the contents of its pipes, to
+/// restore it later.
+///
+/// Taking a snapshot copies the memory of the program. It is nearly free with the default
+/// `PersistentMemory`, whose clones share their cells, but copies every cell of a program moved
+/// to another backend with `with_memory`.
+#[derive(Clone)]
+pub struct Snapshot<V = i64, M = PersistentMemory<V>> {
+    state: ProgramState<V, M>,
+    error: Option<IntcodeError<V>>,
+    input: Vec<V>,
+    output: Option<Vec<V>>,
+}
+
+impl<V: Value, M: Memory<V>> Program<V, M> {
+    /// Takes a snapshot of the program and of the values waiting in its pipes.
+    pub fn snapshot(&self) -> Snapshot<V, M> {
+        Snapshot {
+            state: self.state.clone(),
+            error: self.error.clone(),
+            input: self.input.contents(),
+            output: self.output.as_ref().map(|p| p.contents()),
+        }
+    }
+
+    /// Sets the program back to a snapshot. The pipes of the program are kept, but hold the
+    /// values they held when the snapshot was taken. The history of the program is cleared.
+    pub fn restore(&mut self, snapshot: &Snapshot<V, M>) {
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+        self.state = snapshot.state.clone();
+        self.error = snapshot.error.clone();
+        self.input.set_contents(snapshot.input.clone());
+        if let (Some(output), Some(values)) = (&self.output, &snapshot.output) {
+            output.set_contents(values.clone());
+        }
+    }
+
+    /// Creates an independent copy of the program, with its own pipes holding a copy of the
+    /// values of the pipes of this one. The copy shares the instruction set of the program, but
+    /// not its observers, input source or output sink.
+    pub fn fork(&self) -> Self {
+        Program {
+            state: self.state.clone(),
+            input: self.input.fork(),
+            output: self.output.as_ref().map(|p| p.fork()),
+            error: self.error.clone(),
+            observers: Vec::new(),
+            instructions: self.instructions.clone(),
+            budget: self.budget,
+            executed: self.executed,
+            cycle_detector: self.cycle_detector.clone(),
+            modification_detector: self.modification_detector.clone(),
+            history: self.history.clone(),
+            source: None,
+            sink: None,
+            recent_outputs: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod snapshot_should {
+    use crate::intcode::{squares, PersistentMemory, Pipe, Program, ProgramStatus};
+
+    #[test]
+    fn try_several_inputs_from_the_same_state() {
+        let mut program = Program::<i64, PersistentMemory>::with_memory(squares());
+        let output = Pipe::new();
+        program.set_output(&output);
+        program.write(2);
+        program.run().unwrap();
+        let snapshot = program.snapshot();
+
+        let results: Vec<Option<i64>> = (3..6)
+            .map(|input| {
+                program.restore(&snapshot);
+                program.write(input);
+                program.run().unwrap();
+                output.contents().last().cloned()
+            })
+            .collect();
+
+        assert_eq!(results, vec![Some(9), Some(16), Some(25)]);
+        program.restore(&snapshot);
+        assert_eq!(output.contents(), vec![4]);
+        assert_eq!(program.status(), ProgramStatus::Waiting);
+    }
+
+    #[test]
+    fn restore_the_input_waiting_when_the_snapshot_was_taken() {
+        let mut program = Program::new(squares());
+        program.set_output(&Pipe::new());
+        program.write(2);
+        program.write(0);
+        let snapshot = program.snapshot();
+
+        assert!(program.run().unwrap());
+        program.restore(&snapshot);
+
+        assert_eq!(program.status(), ProgramStatus::Running);
+        assert!(program.run().unwrap());
+        assert_eq!(program.read(), Some(4));
+    }
+
+    #[test]
+    fn fork_a_program_with_its_own_pipes() {
+        let mut program = Program::new(squares());
+        let output = Pipe::new();
+        program.set_output(&output);
+        program.write(2);
+        program.run().unwrap();
+
+        let mut fork = program.fork();
+        fork.write(3);
+        fork.run().unwrap();
+        program.write(0);
+        program.run().unwrap();
+
+        assert_eq!(output.contents(), vec![4]);
+        assert_eq!(fork.output().unwrap().contents(), vec![4, 9]);
+        assert_eq!(program.status(), ProgramStatus::Over);
+        assert_eq!(fork.status(), ProgramStatus::Waiting);
+    }
+}
@@ -0,0 +1,319 @@
+use super::disassembler::{Operand, Statement};
+use super::{Instruction, Memory, OpCode, ParameterMode, ProgramState, Value};
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+/// An observer notified of each instruction executed by a program.
+pub trait ExecutionObserver<V> {
+    fn on_instruction(&mut self, event: &InstructionEvent<V>);
+}
+
+/// Shares an observer, so that it can still be used once added to a program.
+impl<V, O: ExecutionObserver<V>> ExecutionObserver<V> for Rc<RefCell<O>> {
+    fn on_instruction(&mut self, event: &InstructionEvent<V>) {
+        self.borrow_mut().on_instruction(event);
+    }
+}
+
+/// An instruction executed by a program.
+#[derive(Clone, PartialEq, Debug)]
+pub struct InstructionEvent<V = i64> {
+    pub address: usize,
+    pub instruction: Instruction,
+    /// The raw parameters of the instruction.
+    pub parameters: Vec<V>,
+    /// The values of the parameters read by the instruction, after resolving their mode.
+    pub operands: Vec<V>,
+    /// The addresses read by the parameters in position or relative mode.
+    pub reads: Vec<usize>,
+    pub write: Option<MemoryWrite<V>>,
+    pub input: Option<V>,
+    pub output: Option<V>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct MemoryWrite<V = i64> {
+    pub address: usize,
+    pub value: V,
+}
+
+impl<V: Value> InstructionEvent<V> {
+    /// Starts the event of an instruction about to be executed, with what it reads. The value it
+    /// writes is known once it is executed, and given by `finish`.
+    ///
+    /// Parameters are resolved on a best-effort basis, since the instruction may not use them all:
+    /// the ones pointing at an invalid address, such as the target of a jump that is not taken,
+    /// are left out of the operands and the reads, so that observing a program never makes it
+    /// fault.
+    pub(super) fn start<M: Memory<V>>(
+        instruction: Instruction,
+        before: &ProgramState<V, M>,
+    ) -> Self {
+        let address = before.current_position;
+        let opcode = instruction.opcode.clone();
+        let written = opcode.written_parameter();
+        let parameters = (0..opcode.arity())
+            .map(|i| before.intcode.read(address + 1 + i))
+            .collect();
+        let operands = (0..opcode.arity())
+            .filter(|i| Some(*i) != written)
+            .filter_map(|i| {
+                before
+                    .read_parameter(i, instruction.get_parameter_mode(i))
+                    .ok()
+            })
+            .collect::<Vec<V>>();
+        let reads = (0..opcode.arity())
+            .filter(|i| Some(*i) != written)
+            .filter(|i| instruction.get_parameter_mode(*i) != &ParameterMode::Immediate)
+            .filter_map(|i| {
+                before
+                    .parameter_address(i, instruction.get_parameter_mode(i))
+                    .ok()
+            })
+            .collect();
+        let write = written
+            .and_then(|i| {
+                before
+                    .parameter_address(i, instruction.get_parameter_mode(i))
+                    .ok()
+            })
+            .map(|address| MemoryWrite {
+                address,
+                value: V::from(0),
+            });
+        let output = match opcode {
+            OpCode::Output => operands.first().cloned(),
+            _ => None,
+        };
+        InstructionEvent {
+            address,
+            instruction,
+            parameters,
+            operands,
+            reads,
+            write,
+            input: None,
+            output,
+        }
+    }
+
+    /// Completes the event with the value written by the instruction, once executed.
+    pub(super) fn finish<M: Memory<V>>(&mut self, after: &ProgramState<V, M>) {
+        if let Some(write) = &mut self.write {
+            write.value = after.intcode.read(write.address);
+            if self.instruction.opcode == OpCode::Input {
+                self.input = Some(write.value.clone());
+            }
+        }
+    }
+
+    /// The executed instruction, as listed by the disassembler.
+    pub fn statement(&self) -> Statement<V> {
+        let operands = self
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(i, value)| Operand {
+                mode: *self.instruction.get_parameter_mode(i),
+                value: value.clone(),
+            })
+            .collect();
+        Statement::Instruction(self.instruction.opcode.clone(), operands)
+    }
+}
+
+/// An observer writing a line for each executed instruction, so that traces of two runs can be
+/// compared with a diff tool.
+///
+/// Observers cannot fail, so the first write error stops the trace and is kept to be checked
+/// later.
+pub struct TraceWriter<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl TraceWriter<BufWriter<File>> {
+    pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(TraceWriter::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(writer: W) -> Self {
+        TraceWriter {
+            writer,
+            error: None,
+        }
+    }
+
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_event<V: Value>(&mut self, event: &InstructionEvent<V>) -> io::Result<()> {
+        write!(self.writer, "{}: {}", event.address, event.statement())?;
+        if !event.operands.is_empty() {
+            let operands: Vec<String> = event.operands.iter().map(|v| v.to_string()).collect();
+            write!(self.writer, " ; read {}", operands.join(","))?;
+        }
+        if let Some(write) = &event.write {
+            write!(self.writer, " ; write [{}]={}", write.address, write.value)?;
+        }
+        if let Some(input) = &event.input {
+            write!(self.writer, " ; input {}", input)?;
+        }
+        if let Some(output) = &event.output {
+            write!(self.writer, " ; output {}", output)?;
+        }
+        writeln!(self.writer)
+    }
+}
+
+impl<V: Value, W: Write> ExecutionObserver<V> for TraceWriter<W> {
+    fn on_instruction(&mut self, event: &InstructionEvent<V>) {
+        if self.error.is_none() {
+            if let Err(error) = self.write_event(event) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod execution_observer_should {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+    use crate::intcode::{Intcode, Pipe, Program};
+
+    struct Recorder {
+        events: Vec<InstructionEvent>,
+    }
+
+    impl ExecutionObserver<i64> for Recorder {
+        fn on_instruction(&mut self, event: &InstructionEvent) {
+            self.events.push(event.clone());
+        }
+    }
+
+    fn record(program: &mut Program) -> Rc<RefCell<Recorder>> {
+        let recorder = Rc::new(RefCell::new(Recorder { events: Vec::new() }));
+        program.add_observer(Box::new(recorder.clone()));
+        recorder
+    }
+
+    #[test]
+    fn receive_an_event_for_each_executed_instruction() {
+        let mut program = Program::new(Intcode::from(vec![1001, 7, 3, 8, 4, 8, 99, 39, 0]));
+        program.set_output(&Pipe::new());
+        let recorder = record(&mut program);
+
+        program.run().unwrap();
+
+        let events = &recorder.borrow().events;
+        assert_eq!(
+            events.iter().map(|e| e.address).collect::<Vec<_>>(),
+            vec![0, 4, 6]
+        );
+        assert_eq!(
+            events[0],
+            InstructionEvent {
+                address: 0,
+                instruction: Instruction::from(&1001).unwrap(),
+                parameters: vec![7, 3, 8],
+                operands: vec![39, 3],
+                reads: vec![7],
+                write: Some(MemoryWrite {
+                    address: 8,
+                    value: 42
+                }),
+                input: None,
+                output: None,
+            }
+        );
+        assert_eq!(events[1].output, Some(42));
+        assert_eq!(events[2].instruction.opcode(), &OpCode::Halt);
+    }
+
+    #[test]
+    fn not_make_a_program_fault_on_a_parameter_it_does_not_use() {
+        // A jump not taken, whose target points at an invalid address.
+        let intcode = Intcode::from(vec![105, 0, -1, 99]);
+        let mut unobserved = Program::new(intcode.clone());
+        let mut observed = Program::new(intcode);
+        let recorder = record(&mut observed);
+
+        let unobserved = unobserved.run();
+        let observed = observed.run();
+
+        assert_eq!(unobserved, Ok(true));
+        assert_eq!(observed, Ok(true));
+        assert_eq!(recorder.borrow().events[0].operands, vec![0]);
+    }
+
+    #[test]
+    fn receive_the_input_read_but_no_event_while_waiting() {
+        let mut program = Program::new(Intcode::from(vec![203, 5, 99]));
+        let recorder = record(&mut program);
+
+        program.run().unwrap();
+        program.write(12);
+        program.run().unwrap();
+
+        let events = &recorder.borrow().events;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].input, Some(12));
+        assert_eq!(
+            events[0].write,
+            Some(MemoryWrite {
+                address: 5,
+                value: 12
+            })
+        );
+        assert_eq!(
+            events[0].instruction.get_parameter_mode(0),
+            &ParameterMode::Relative
+        );
+    }
+
+    #[test]
+    fn write_a_trace_line_for_each_instruction() {
+        let mut program: Program = Program::new(
+            assemble(
+                "in [value]
+                 mul [value], #3, [value]
+                 out [value]
+                 hlt
+                 value: .data 0",
+            )
+            .unwrap(),
+        );
+        program.set_output(&Pipe::new());
+        let trace = Rc::new(RefCell::new(TraceWriter::new(Vec::new())));
+        program.add_observer(Box::new(trace.clone()));
+        program.write(5);
+
+        program.run().unwrap();
+
+        let trace = trace.borrow();
+        assert!(trace.error().is_none());
+        assert_eq!(
+            String::from_utf8(trace.get_ref().clone()).unwrap(),
+            "0: in [9] ; write [9]=5 ; input 5\n\
+             2: mul [9], #3, [9] ; read 5,3 ; write [9]=15\n\
+             6: out [9] ; read 15 ; output 15\n\
+             8: hlt\n"
+        );
+    }
+}
@@ -1,25 +1,29 @@
+use advent2019::intcode::ascii::{AsciiInput, AsciiOutput};
+use advent2019::intcode::control_flow::ControlFlowGraph;
+use advent2019::intcode::debugger::{run_repl, Debugger};
+use advent2019::intcode::disassembler::disassemble;
+use advent2019::intcode::{is_saved_program, HistoryLimits, Intcode, Pipe, Program, ProgramStatus};
+use advent2019::{day1, day2, day3, day4, day5, day6, day7};
 use im_rc::Vector;
 use std::env;
-use std::io::{stdin, stdout, Result, Write};
-use std::time::Instant;
-
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod input;
-mod intcode;
+use std::fs::read_to_string;
+use std::io::{stdin, stdout, BufRead, Error, ErrorKind, Result, Write};
+use std::time::{Duration, Instant};
 
 fn main() -> Result<()> {
     let args: Vector<String> = env::args().collect();
-    if args.len() > 1 {
-        execute_day(&args[1]);
-    } else {
-        let day = read_console_input()?;
-        execute_day(&day);
+    match args.get(1).map(String::as_str) {
+        Some("disassemble") if args.len() > 2 => print_listing(&args[2])?,
+        Some("debug") if args.len() > 2 => debug(&args[2])?,
+        Some("cfg") if args.len() > 2 => print_control_flow_graph(&args[2])?,
+        Some("run") if args.len() > 2 => {
+            run(&args[2], args.iter().skip(3).any(|a| a == "--ascii"))?
+        }
+        Some(_) => execute_day(&args[1]),
+        None => {
+            let day = read_console_input()?;
+            execute_day(&day);
+        }
     }
     Ok(())
 }
@@ -32,9 +36,133 @@ fn read_console_input() -> Result<String> {
     Ok(line)
 }
 
-fn execute_day(day: &String) {
+fn read_intcode(file: &str) -> Result<Intcode> {
+    read_to_string(file)?
+        .parse()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+fn print_listing(file: &str) -> Result<()> {
+    let intcode = read_intcode(file)?;
+    print!("{}", disassemble(&intcode));
+    Ok(())
+}
+
+fn print_control_flow_graph(file: &str) -> Result<()> {
+    let intcode = read_intcode(file)?;
+    print!("{}", ControlFlowGraph::build(&intcode).to_dot());
+    Ok(())
+}
+
+/// Reads a program from a file holding either intcode or a program saved by the debugger.
+fn read_program(file: &str) -> Result<Program> {
+    let source = read_to_string(file)?;
+    let mut program = match is_saved_program(&source) {
+        true => Program::load(&source).map_err(|e| Error::new(ErrorKind::InvalidData, e))?,
+        false => Program::new(
+            source
+                .parse()
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?,
+        ),
+    };
+    if program.output().is_none() {
+        program.set_output(&Pipe::new());
+    }
+    Ok(program)
+}
+
+fn debug(file: &str) -> Result<()> {
+    let mut program = read_program(file)?;
+    program.set_history(Some(HistoryLimits::default()));
+    let mut debugger = Debugger::new(program);
+    run_repl(&mut debugger, stdin().lock(), stdout())
+}
+
+/// The number of instructions a running program executes before its outputs are printed.
+const OUTPUT_INTERVAL: usize = 10_000;
+
+/// Runs a program, feeding it the lines read from the standard input each time it waits for
+/// input, as values separated by commas or spaces, or as text in ASCII mode. Outputs are printed
+/// as they appear, and a report to the standard error once the program stops.
+fn run(file: &str, ascii: bool) -> Result<()> {
+    let mut program = read_program(file)?;
+    program.set_step_budget(Some(OUTPUT_INTERVAL));
+    let output = program.output().unwrap().clone();
+    let text_input = AsciiInput::new(program.input());
+    let text_output = AsciiOutput::new(&output);
+    let mut lines = stdin().lock().lines();
+    let mut elapsed = Duration::default();
+    let mut line_ended = true;
+    let result = loop {
+        let start = Instant::now();
+        let result = program.run();
+        elapsed += start.elapsed();
+        match ascii {
+            true => {
+                let text = text_output.read_string();
+                if !text.is_empty() {
+                    line_ended = text.ends_with('\n');
+                }
+                print!("{}", text);
+            }
+            false => {
+                while let Some(value) = output.read() {
+                    println!("{}", value);
+                }
+            }
+        }
+        stdout().flush()?;
+        match (result, program.status()) {
+            (Err(error), _) => break Err(error),
+            (Ok(_), ProgramStatus::BudgetExhausted) => (),
+            (Ok(_), ProgramStatus::Waiting) => match lines.next() {
+                Some(line) => {
+                    let line = line?;
+                    let written = match ascii {
+                        true => text_input.write_line(&line).map_err(|e| e.to_string()),
+                        false => write_values(&program, &line),
+                    };
+                    if let Err(message) = written {
+                        eprintln!("{}", message);
+                    }
+                }
+                None => break Ok(()),
+            },
+            (Ok(_), _) => break Ok(()),
+        }
+    };
+    if !line_ended {
+        println!();
+    }
+    match result {
+        Ok(()) if program.status() == ProgramStatus::Over => eprintln!("Program is over"),
+        Ok(()) => eprintln!("Program is waiting for input, but the input has ended"),
+        Err(error) => eprintln!("Program faulted: {}", error),
+    }
+    eprintln!(
+        "{} instructions executed in {:?}",
+        program.instruction_count(),
+        elapsed
+    );
+    Ok(())
+}
+
+/// Writes the values of a line to the input of a program, or none if one is invalid.
+fn write_values(program: &Program, line: &str) -> std::result::Result<(), String> {
+    let values = line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse().map_err(|_| format!("Invalid value: {}", v)))
+        .collect::<std::result::Result<Vec<i64>, String>>()?;
+    for value in values {
+        program.write(value);
+    }
+    Ok(())
+}
+
+fn execute_day(day: &str) {
     let start = Instant::now();
-    match day.trim().as_ref() {
+    match day.trim() {
         "1" => day1::execute(),
         "2" => day2::execute(),
         "3" => day3::execute(),